@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum ArmorType {
     None,
     Leather,
@@ -6,6 +6,30 @@ pub enum ArmorType {
     Plate,
 }
 
+impl ArmorType {
+    /// Return the armor's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArmorType::None => "NONE",
+            ArmorType::Leather => "LEATHER",
+            ArmorType::Chainmail => "CHAINMAIL",
+            ArmorType::Plate => "PLATE",
+        }
+    }
+
+    /// Return the armor with a name
+    pub fn from_name(name: &str) -> Option<ArmorType> {
+        match name {
+            "NONE" => Some(ArmorType::None),
+            "LEATHER" => Some(ArmorType::Leather),
+            "CHAINMAIL" => Some(ArmorType::Chainmail),
+            "PLATE" => Some(ArmorType::Plate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Armor {
     armor_type: ArmorType,
     health: u32,
@@ -14,16 +38,19 @@ pub struct Armor {
 impl Armor {
     /// Create a new armor
     pub fn new(a: ArmorType) -> Armor {
-        let health = match a {
+        Armor {
+            armor_type: a,
+            health: Armor::max_health(a),
+        }
+    }
+
+    /// Return how much damage a new armor can take
+    fn max_health(a: ArmorType) -> u32 {
+        match a {
             ArmorType::None => 0,    // 0 * 7
             ArmorType::Leather => 7, // 1 * 7
             ArmorType::Chainmail => 2 * 7,
             ArmorType::Plate => 3 * 7,
-        };
-
-        Armor {
-            armor_type: a,
-            health,
         }
     }
 
@@ -79,6 +106,22 @@ impl Armor {
         self.armor_type
     }
 
+    /// True if the armor has taken any damage
+    pub fn is_damaged(&self) -> bool {
+        self.health < Armor::max_health(self.armor_type)
+    }
+
+    /// Repair the armor, but not past new
+    ///
+    /// Return how much was repaired.
+    pub fn repair(&mut self, amount: u32) -> u32 {
+        let repaired = std::cmp::min(amount, Armor::max_health(self.armor_type) - self.health);
+
+        self.health += repaired;
+
+        repaired
+    }
+
     /// Damage the armor
     ///
     /// Return true if the armor is destroyed
@@ -4,21 +4,38 @@ use rand::rngs::ThreadRng;
 use rand::thread_rng;
 use rand::Rng;
 
-use wizardscastle::armor::{Armor, ArmorType};
+use wizardscastle::armor::ArmorType;
+use wizardscastle::castle;
+use wizardscastle::curse::CurseType;
+use wizardscastle::difficulty::Difficulty;
+use wizardscastle::dungeon::{Dungeon, Edge};
 use wizardscastle::error::Error;
+use wizardscastle::export;
 use wizardscastle::game::{
-    BookEvent, ChestEvent, DrinkEvent, GameState, HitResult, OrbEvent, RandomMessage,
+    BookEvent, BribeOffer, ChestEvent, DrinkEvent, GameState, HitResult, OrbEvent, RandomMessage,
 };
-use wizardscastle::game::{CombatEvent, Direction, Event, Game, Stairs};
+use wizardscastle::game::{CombatEvent, DeathCause, Direction, Event, Game, Outcome, Stairs};
+use wizardscastle::generator::{
+    ClassicGenerator, DepthGenerator, DungeonGenerator, MazeGenerator, VaultGenerator,
+};
+use wizardscastle::highscore::{self, HighScore, HighScoreTable};
+use wizardscastle::history::{History, RunRecord};
+use wizardscastle::monster::Monster;
 use wizardscastle::monster::MonsterType;
 use wizardscastle::player::{Gender, Race, Stat};
-use wizardscastle::room::RoomType;
-use wizardscastle::treasure::TreasureType;
-use wizardscastle::weapon::{Weapon, WeaponType};
+use wizardscastle::rng;
+use wizardscastle::room::{Room, RoomType};
+use wizardscastle::treasure::{Treasure, TreasureType, TREASURE_COUNT};
+use wizardscastle::vendor::Goods;
+use wizardscastle::weapon::WeaponType;
+
+/// How many turns before a remembered room is shown as out of date
+const STALE_TURNS: u32 = 50;
 
 struct UI {
     game: Game,
     rng: ThreadRng,
+    debug: bool,
 }
 
 impl UI {
@@ -45,21 +62,7 @@ impl UI {
     }
 
     fn monster_name(m: MonsterType) -> String {
-        match m {
-            MonsterType::Kobold => String::from("KOBOLD"),
-            MonsterType::Orc => String::from("ORC"),
-            MonsterType::Wolf => String::from("WOLF"),
-            MonsterType::Goblin => String::from("GOBLIN"),
-            MonsterType::Ogre => String::from("OGRE"),
-            MonsterType::Troll => String::from("TROLL"),
-            MonsterType::Bear => String::from("BEAR"),
-            MonsterType::Minotaur => String::from("MINOTAUR"),
-            MonsterType::Gargoyle => String::from("GARGOYLE"),
-            MonsterType::Chimera => String::from("CHIMERA"),
-            MonsterType::Balrog => String::from("BALROG"),
-            MonsterType::Dragon => String::from("DRAGON"),
-            MonsterType::Vendor => String::from("VENDOR"),
-        }
+        String::from(m.name())
     }
 
     fn stat_name(s: Stat) -> String {
@@ -95,6 +98,15 @@ impl UI {
         }
     }
 
+    fn direction_name(d: Direction) -> String {
+        match d {
+            Direction::North => String::from("NORTH"),
+            Direction::South => String::from("SOUTH"),
+            Direction::West => String::from("WEST"),
+            Direction::East => String::from("EAST"),
+        }
+    }
+
     fn treasure_name(t: TreasureType) -> String {
         match t {
             TreasureType::RubyRed => String::from("THE RUBY RED"),
@@ -127,16 +139,37 @@ impl UI {
                 format!("{} {}", UI::get_article(&mon_str), mon_str)
             }
             RoomType::Treasure(t) => UI::treasure_name(*t.treasure_type()),
+            RoomType::Vault(_) => String::from("A LOCKED VAULT"),
+            RoomType::PitTrap => String::from("A PIT TRAP"),
+            RoomType::DartTrap => String::from("A DART TRAP"),
+            RoomType::AlarmTrap => String::from("AN ALARM TRAP"),
+            RoomType::SlimeTrap => String::from("A SLIME TRAP"),
         }
     }
 
-    fn gender_name(g: Gender) -> String {
-        match g {
-            Gender::Female => String::from("FEMALE"),
-            Gender::Male => String::from("MALE"),
+    /// Name everything the player can see in a room
+    fn contents_name(room: &Room) -> String {
+        let names: Vec<String> = room
+            .seen_contents()
+            .into_iter()
+            .map(UI::room_name)
+            .collect();
+
+        if names.is_empty() {
+            UI::room_name(&RoomType::Empty)
+        } else {
+            names.join(" AND ")
         }
     }
 
+    fn difficulty_name(d: Difficulty) -> String {
+        String::from(d.name())
+    }
+
+    fn gender_name(g: Gender) -> String {
+        String::from(g.name())
+    }
+
     fn starts_with_vowel(s: &str) -> bool {
         if let Some(c) = String::from(s).to_uppercase().chars().next() {
             return c == 'A' || c == 'E' || c == 'I' || c == 'O' || c == 'U';
@@ -154,12 +187,58 @@ impl UI {
     }
 
     /// Move a direction
-    fn move_dir(&mut self, dir: Direction) {
-        self.game.move_dir(dir);
+    fn move_dir(&mut self, dir: Direction) -> bool {
+        match self.game.move_dir(dir) {
+            Ok(true) => println!("YOU UNLOCK IT WITH A KEY\n"),
+            Ok(false) => (),
+            Err(err) => {
+                self.blocked(err);
+                return false;
+            }
+        }
 
         // This is often redundant, but there's a case where we retreat from
         // monsters and the discover room gets overlooked
         self.game.discover_room_at_player();
+
+        true
+    }
+
+    /// Say why the player can't go that way
+    ///
+    /// This wasn't in the original game.
+    fn blocked(&self, err: Error) {
+        match err {
+            Error::Locked => println!("** IT'S LOCKED AND YOU HAVE NO KEY, {}", self.race_str()),
+            _ => println!("** YOU CAN'T WALK THROUGH WALLS, {}", self.race_str()),
+        }
+    }
+
+    /// Return the map character for an edge between rooms
+    ///
+    /// Secret doors look like walls until they're found. This wasn't in the
+    /// original game.
+    fn edge_char(edge: Edge, vertical: bool) -> char {
+        match edge {
+            Edge::Open => ' ',
+            Edge::Wall | Edge::SecretDoor => {
+                if vertical {
+                    '|'
+                } else {
+                    '-'
+                }
+            }
+            Edge::Door => '+',
+            Edge::LockedDoor => '#',
+        }
+    }
+
+    /// Return the map string for the edge below a room
+    fn edge_str_south(edge: Edge) -> String {
+        match edge {
+            Edge::Open => String::from("   "),
+            _ => format!("-{}-", UI::edge_char(edge, false)),
+        }
     }
 
     /// Take some stairs
@@ -176,8 +255,8 @@ impl UI {
         true
     }
 
-    // Input a coordinate, 1-8
-    fn input_coord(prompt: &str) -> u32 {
+    // Input a coordinate, 1-max
+    fn input_coord(prompt: &str, max: u32) -> u32 {
         let mut coord = 0;
         let mut got_num = false;
 
@@ -185,14 +264,14 @@ impl UI {
             let str = UI::get_input(Some(prompt));
 
             if let Ok(v) = str.parse::<u32>() {
-                if (1..=8).contains(&v) {
+                if (1..=max).contains(&v) {
                     got_num = true;
                     coord = v;
                 }
             }
 
             if !got_num {
-                println!("\n** TRY A NUMBER FROM 1 TO 8\n");
+                println!("\n** TRY A NUMBER FROM 1 TO {}\n", max);
             }
         }
 
@@ -206,9 +285,24 @@ impl UI {
             return false;
         }
 
-        let x = UI::input_coord("X-COORD (1 = FAR WEST  8 = FAR EAST )? ");
-        let y = UI::input_coord("Y-COORD (1 = FAR NORTH 8 = FAR SOUTH)? ");
-        let z = UI::input_coord("Z-COORD (1 = TOP       8 = BOTTOM   )? ");
+        let (xsize, ysize, zsize) = (
+            self.game.dungeon_xsize(),
+            self.game.dungeon_ysize(),
+            self.game.dungeon_zsize(),
+        );
+
+        let x = UI::input_coord(
+            &format!("X-COORD (1 = FAR WEST  {} = FAR EAST )? ", xsize),
+            xsize,
+        );
+        let y = UI::input_coord(
+            &format!("Y-COORD (1 = FAR NORTH {} = FAR SOUTH)? ", ysize),
+            ysize,
+        );
+        let z = UI::input_coord(
+            &format!("Z-COORD (1 = TOP       {} = BOTTOM   )? ", zsize),
+            zsize,
+        );
 
         match self.game.teleport(x - 1, y - 1, z - 1) {
             // back to 0-based
@@ -219,6 +313,10 @@ impl UI {
                     println!("THE RUNESTAFF IS GONE\n");
                 }
             }
+            Err(Error::Locked) => {
+                println!("\n** THAT'S A LOCKED VAULT, AND YOU HAVE NO KEY");
+                return false;
+            }
             Err(err) => panic!("{:#?}", err),
         }
 
@@ -227,43 +325,25 @@ impl UI {
 
     /// Drink
     fn drink(&mut self) {
-        let s;
-
-        match self.game.drink() {
-            Ok(DrinkEvent::Stronger) => {
-                s = String::from("FEEL STRONGER");
-            }
-            Ok(DrinkEvent::Weaker) => {
-                s = String::from("FEEL WEAKER");
-            }
-            Ok(DrinkEvent::Smarter) => {
-                s = String::from("FEEL SMARTER");
-            }
-            Ok(DrinkEvent::Dumber) => {
-                s = String::from("FEEL DUMBER");
-            }
-            Ok(DrinkEvent::Nimbler) => {
-                s = String::from("FEEL NIMBLER");
-            }
-            Ok(DrinkEvent::Clumsier) => {
-                s = String::from("FEEL CLUMSIER");
-            }
-            Ok(DrinkEvent::ChangeRace) => {
-                s = format!("TURN INTO A {}", self.race_str());
-            }
-            Ok(DrinkEvent::ChangeGender) => {
-                s = format!(
-                    "TURN INTO A {} {}",
-                    UI::gender_name(*self.game.player_gender()),
-                    self.race_str()
-                );
-            }
+        let s = match self.game.drink() {
+            Ok(DrinkEvent::Stronger) => String::from("FEEL STRONGER"),
+            Ok(DrinkEvent::Weaker) => String::from("FEEL WEAKER"),
+            Ok(DrinkEvent::Smarter) => String::from("FEEL SMARTER"),
+            Ok(DrinkEvent::Dumber) => String::from("FEEL DUMBER"),
+            Ok(DrinkEvent::Nimbler) => String::from("FEEL NIMBLER"),
+            Ok(DrinkEvent::Clumsier) => String::from("FEEL CLUMSIER"),
+            Ok(DrinkEvent::ChangeRace) => format!("TURN INTO A {}", self.race_str()),
+            Ok(DrinkEvent::ChangeGender) => format!(
+                "TURN INTO A {} {}",
+                UI::gender_name(*self.game.player_gender()),
+                self.race_str()
+            ),
             Err(Error::CantGo) => {
                 println!("** IF YOU WANT A DRINK, FIND A POOL");
                 return;
             }
             Err(err) => panic!("{:#?}", err),
-        }
+        };
 
         println!("YOU TAKE A DRINK AND {}", s);
     }
@@ -291,6 +371,10 @@ impl UI {
                 }
             }
             RoomType::Treasure(_) => 'T',
+            RoomType::Vault(_) => 'L',
+            RoomType::PitTrap | RoomType::DartTrap | RoomType::AlarmTrap | RoomType::SlimeTrap => {
+                '^'
+            }
         }
     }
 
@@ -303,12 +387,38 @@ impl UI {
 
         let z = self.game.player_z();
 
-        for y in 0..self.game.dungeon_ysize() {
-            for x in 0..self.game.dungeon_xsize() {
+        let (xsize, ysize) = (self.game.dungeon_xsize(), self.game.dungeon_ysize());
+
+        // Edges show up once a room on either side of them has been seen
+        let seen = |x: u32, y: u32| show_all || self.game.dungeon_room_at(x, y, z).discovered;
+
+        let edge = |x: u32, y: u32, dir: Direction, (nx, ny): (u32, u32)| {
+            if seen(x, y) || seen(nx, ny) {
+                self.game.dungeon_edge(x, y, z, dir)
+            } else {
+                Edge::Open
+            }
+        };
+
+        // Remembered rooms that might have changed since, and how long ago
+        // they were seen
+        let mut stale = Vec::new();
+
+        for y in 0..ysize {
+            let mut below = String::new();
+
+            for x in 0..xsize {
                 if x >= 1 {
-                    print!("   ");
+                    let west = edge(x, y, Direction::West, (x - 1, y));
+
+                    print!(" {} ", UI::edge_char(west, true));
+                    below.push_str("   ");
                 }
 
+                let south = edge(x, y, Direction::South, (x, (y + 1) % ysize));
+
+                below.push_str(&UI::edge_str_south(south));
+
                 let r = self.game.dungeon_room_at(x, y, z);
 
                 let bracket = x == self.game.player_x() && y == self.game.player_y();
@@ -319,8 +429,27 @@ impl UI {
                     print!(" ");
                 }
 
-                if r.discovered || show_all {
-                    print!("{}", UI::room_char(&r.roomtype));
+                // Other rooms show what the player remembers of them, which
+                // might be out of date
+                if show_all {
+                    print!("{}", UI::room_char(r.room_type()));
+                } else if bracket {
+                    print!("{}", UI::room_char(r.seen_type()));
+                } else if let Some(m) = r.memory() {
+                    let c = UI::room_char(m.room_type());
+                    let age = m.age(*self.game.turn());
+
+                    if age >= STALE_TURNS {
+                        if *m.room_type() != RoomType::Empty {
+                            stale.push((x, y, UI::room_name(m.room_type()), age));
+                        }
+
+                        print!("{}", c.to_ascii_lowercase());
+                    } else {
+                        print!("{}", c);
+                    }
+                } else if r.discovered {
+                    print!("{}", UI::room_char(r.seen_type()));
                 } else {
                     print!("?");
                 }
@@ -332,17 +461,37 @@ impl UI {
                 }
             }
 
-            println!("\n");
+            // The castle wraps around, so the last column has an edge on
+            // its east side too
+            let east = edge(xsize - 1, y, Direction::East, (0, y));
+
+            if east != Edge::Open {
+                print!(" {}", UI::edge_char(east, true));
+            }
+
+            println!("\n{}", below.trim_end());
+        }
+
+        if !stale.is_empty() {
+            println!(
+                "LOWERCASE ROOMS HAVEN'T BEEN SEEN IN {} TURNS OR MORE:\n",
+                STALE_TURNS
+            );
+
+            for (x, y, name, age) in stale {
+                println!("({},{}) {}, {} TURNS AGO", x + 1, y + 1, name, age);
+            }
+
+            println!();
         }
     }
 
+    fn race_name(race: Race) -> &'static str {
+        race.name()
+    }
+
     fn race_str(&self) -> &str {
-        match self.game.player_race() {
-            Race::Hobbit => "HOBBIT",
-            Race::Elf => "ELF",
-            Race::Human => "HUMAN",
-            Race::Dwarf => "DWARF",
-        }
+        UI::race_name(*self.game.player_race())
     }
 
     /// Input a line of text
@@ -403,6 +552,25 @@ impl UI {
         println!("\n{:*^64}\n", "");
     }
 
+    /// Select the difficulty level
+    ///
+    /// This wasn't in the original game
+    fn difficulty_select() -> Difficulty {
+        loop {
+            let difficulty_str = UI::get_input(Some(
+                "WHICH DIFFICULTY (EASY, CLASSIC, HARD, OR NIGHTMARE)? ",
+            ));
+
+            match difficulty_str.get(..1) {
+                Some("E") => break Difficulty::Easy,
+                Some("C") | None => break Difficulty::Classic,
+                Some("H") => break Difficulty::Hard,
+                Some("N") => break Difficulty::Nightmare,
+                _ => println!("\n** PLEASE TYPE E, C, H, OR N.\n"),
+            }
+        }
+    }
+
     /// Select the player's race and sex
     fn race_gender_select(&mut self) {
         let race = loop {
@@ -451,8 +619,8 @@ impl UI {
             self.game.player_additional_points()
         );
 
-        let stats = vec![Stat::Intelligence, Stat::Strength, Stat::Dexterity];
-        let stat_names = vec!["INTELLIGENCE", "STRENGTH", "DEXTERITY"];
+        let stats = [Stat::Intelligence, Stat::Strength, Stat::Dexterity];
+        let stat_names = ["INTELLIGENCE", "STRENGTH", "DEXTERITY"];
 
         for i in 0..3 {
             let mut ok = false;
@@ -463,10 +631,8 @@ impl UI {
                     stat_names[i]
                 )));
 
-                let points_to_add;
-
-                match s.parse::<u32>() {
-                    Ok(p) => points_to_add = p,
+                let points_to_add = match s.parse::<u32>() {
+                    Ok(p) => p,
                     Err(_) => {
                         print!("\n** ");
                         continue;
@@ -588,10 +754,8 @@ impl UI {
         loop {
             let flare_str = UI::get_input(Some("FLARES COST 1 GP EACH, HOW MANY DO YOU WANT? "));
 
-            let flare_count;
-
-            match flare_str.parse::<u32>() {
-                Ok(f) => flare_count = f,
+            let flare_count = match flare_str.parse::<u32>() {
+                Ok(f) => f,
                 Err(_) => {
                     print!("** IF YOU DON'T WANT ANY JUST TYPE 0 (ZERO)\n\n");
                     continue;
@@ -640,20 +804,82 @@ impl UI {
         let w_name = UI::weapon_name(self.game.player_weapon_type());
         let a_name = UI::armor_name(self.game.player_armor_type());
 
-        print!("{} / {}", w_name, a_name);
+        if self.game.player_weapon_worn() {
+            print!("{} (WORN) / {}", w_name, a_name);
+        } else {
+            print!("{} / {}", w_name, a_name);
+        }
 
         if self.game.player_has_lamp() {
             print!(" / A LAMP");
         }
 
+        match self.game.player_keys() {
+            0 => (),
+            1 => print!(" / A KEY"),
+            n => print!(" / {} KEYS", n),
+        }
+
+        if self.game.player_poisoned() {
+            print!(" / POISONED");
+        }
+
         println!("\n");
     }
 
+    /// Say what a trap did
+    ///
+    /// Returns true if the player ended up somewhere else that needs looking
+    /// at. Otherwise the rest of the room still has to be dealt with.
+    /// This wasn't in the original game.
+    fn trap(&self, event: Event) -> bool {
+        match event {
+            Event::PitTrap(damage) => {
+                println!("\nTHE FLOOR GIVES WAY! YOU FALL INTO A PIT");
+                println!("AND TAKE {} DAMAGE", damage);
+                true
+            }
+            Event::DartTrap(None) => {
+                println!("\nA DART WHIZZES PAST YOUR HEAD!");
+                false
+            }
+            Event::DartTrap(Some(damage)) => {
+                println!("\nA POISON DART HITS YOU FOR {} DAMAGE!", damage);
+                false
+            }
+            Event::AlarmTrap(monsters) if monsters.is_empty() => {
+                println!("\nAN ALARM BELL RINGS, BUT NOTHING COMES");
+                false
+            }
+            Event::AlarmTrap(monsters) => {
+                println!("\nAN ALARM BELL RINGS!");
+
+                for m in monsters {
+                    let mon_str = UI::monster_name(m);
+                    println!("{} {} COMES RUNNING", UI::get_article(&mon_str), mon_str);
+                }
+
+                false
+            }
+            Event::SlimeTrap(true) => {
+                println!("\nSLIME DRIPS FROM THE CEILING AND EATS YOUR ARMOR AWAY!");
+                false
+            }
+            Event::SlimeTrap(false) => {
+                if self.game.player_armor_type() == ArmorType::None {
+                    println!("\nSLIME DRIPS FROM THE CEILING ONTO YOUR BARE SKIN");
+                } else {
+                    println!("\nSLIME DRIPS FROM THE CEILING AND CORRODES YOUR ARMOR");
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
     /// Print the current room
     fn print_room(&mut self) {
-        let room = self.game.room_at_player();
-
-        let room_str = UI::room_name(&room.roomtype);
+        let room_str = UI::contents_name(self.game.room_at_player());
 
         println!("HERE YOU FIND {}", room_str);
     }
@@ -670,7 +896,11 @@ impl UI {
 
                 let i = self.rng.gen_range(0..suffix.len());
 
-                println!("\nYOU SPEND AN HOUR EATING {}{}", m_name, suffix[i]);
+                println!(
+                    "\nYOU SPEND AN HOUR EATING {}{}",
+                    UI::monster_name(result.monster_type),
+                    suffix[i]
+                );
             }
 
             if result.killed_vendor {
@@ -685,81 +915,256 @@ impl UI {
                     println!("A LAMP");
                 }
             } else {
-                if result.got_runestaff {
+                let loot = result.loot;
+
+                if loot.runestaff {
                     println!("\nGREAT ZOT! YOU'VE FOUND THE RUNESTAFF");
                 }
 
-                println!("\nYOU NOW GET HIS HOARD OF {} GP's", result.treasure);
+                if loot.key {
+                    println!("\nYOU FIND A KEY ON THE BODY");
+                }
+
+                if let Some(treasure) = loot.treasure {
+                    println!("\nHE WAS CARRYING THE {}!", UI::treasure_name(treasure));
+                }
+
+                if loot.flares == 1 {
+                    println!("\nYOU FIND A FLARE");
+                } else if loot.flares > 1 {
+                    println!("\nYOU FIND {} FLARES", loot.flares);
+                }
+
+                if let Some((stat, _)) = loot.potion {
+                    let stat_name = UI::stat_name(stat);
+
+                    println!("\nYOU FIND A POTION OF {} AND DRINK IT", stat_name);
+                    println!(
+                        "\nYOUR {} IS NOW {}",
+                        stat_name,
+                        self.game.player_stat(stat)
+                    );
+                }
+
+                if loot.armor_repaired > 0 {
+                    println!("\nYOU FIND SOME RIVETS AND PATCH UP YOUR ARMOR");
+                }
+
+                if loot.weapon_repaired > 0 {
+                    println!(
+                        "\nYOU FIND A WHETSTONE AND TOUCH UP YOUR {}",
+                        UI::weapon_name(self.game.player_weapon_type())
+                    );
+                }
+
+                println!("\nYOU NOW GET HIS HOARD OF {} GP's", loot.gold);
+            }
+        }
+    }
+
+    /// Return the article and name of each monster being fought
+    ///
+    /// When there's more than one, they're numbered so the player can tell them
+    /// apart.
+    fn fighting_names(&self) -> Vec<(String, String)> {
+        let fighting = self.game.fighting();
+
+        fighting
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let name = UI::monster_name(m.monster_type());
+
+                if fighting.len() > 1 {
+                    (String::from("THE"), format!("{} #{}", name, i + 1))
+                } else {
+                    (UI::get_article(&name), name)
+                }
+            })
+            .collect()
+    }
+
+    /// Ask which monster to go after, if there's more than one
+    ///
+    /// Returns false if the player didn't pick one.
+    fn combat_target(&mut self) -> bool {
+        let count = self.game.fighting().len();
+
+        if count < 2 {
+            return true;
+        }
+
+        let choice = UI::get_input(Some(&format!("\nWHICH ONE (1-{})? ", count)));
+
+        match choice.parse::<usize>() {
+            Ok(n) if n > 0 && self.game.set_target(n - 1).is_ok() => true,
+            _ => {
+                println!("\n** CHOOSE ONE OF THE MONSTERS LISTED");
+                false
             }
         }
     }
 
     // Attack a monster
-    fn combat_attack(&mut self, m_art: &str, m_name: &str) {
+    fn combat_attack(&mut self) {
+        if !self.combat_target() {
+            return;
+        }
+
         // Need to do this before the attack since the weapon might
-        // break during it
+        // break during it, and the monster might die
         let weapon_type = self.game.player_weapon_type();
+        let names = self.fighting_names();
+        let (m_art, m_name) = &names[self.game.target()];
 
-        match self.game.attack() {
-            Ok(CombatEvent::NoWeapon) => {
-                println!("\n** POUNDING ON {} {} WON'T HURT IT", m_art, m_name);
-            }
+        let events = match self.game.attack() {
+            Ok(events) => events,
+            Err(err) => panic!("error in combat {:#?}", err),
+        };
 
-            Ok(CombatEvent::BookHands) => {
-                println!("\n** YOU CAN'T BEAT IT TO DEATH WITH A BOOK");
-            }
+        for event in events {
+            match event {
+                CombatEvent::NoWeapon => {
+                    println!("\n** POUNDING ON {} {} WON'T HURT IT", m_art, m_name);
+                }
 
-            Ok(CombatEvent::Hit(result)) => {
-                println!("\n  YOU HIT THE LOUSY {}", m_name);
+                CombatEvent::BookHands => {
+                    println!("\n** YOU CAN'T BEAT IT TO DEATH WITH A BOOK");
+                }
 
-                if result.broke_weapon {
-                    println!("\nOH NO! YOUR {} BROKE", UI::weapon_name(weapon_type));
+                CombatEvent::MeleeResisted(_) => {
+                    println!("\n  YOUR BLOW GLANCES OFF ITS STONY HIDE");
                 }
 
-                self.monster_defeated_message(result, m_art, m_name);
-            }
+                CombatEvent::Hit(result) => {
+                    println!("\n  YOU HIT THE LOUSY {}", m_name);
 
-            Ok(CombatEvent::Miss) => {
-                println!("\n  DRAT! MISSED");
-            }
+                    if result.broke_weapon {
+                        println!("\nOH NO! YOUR {} BROKE", UI::weapon_name(weapon_type));
+                    }
 
-            Ok(any) => panic!("unexpected combat event {:#?}", any),
+                    self.monster_defeated_message(result, m_art, m_name);
+                }
 
-            Err(err) => panic!("error in combat {:#?}", err),
+                CombatEvent::Miss => {
+                    println!("\n  DRAT! MISSED");
+                }
+
+                CombatEvent::WeaponWorn => {
+                    println!(
+                        "\nYOUR {} IS GETTING WORN FROM ALL THIS FIGHTING",
+                        UI::weapon_name(weapon_type)
+                    );
+                }
+
+                CombatEvent::WeaponWornOut => {
+                    println!(
+                        "\nYOUR {} FINALLY FALLS TO PIECES",
+                        UI::weapon_name(weapon_type)
+                    );
+                }
+
+                any => panic!("unexpected combat event {:#?}", any),
+            }
         }
     }
 
-    /// Be attacked by a monster
-    fn combat_be_attacked(&mut self, m_name: &str) {
-        match self.game.be_attacked() {
-            Ok(CombatEvent::MonsterWebbed) => {
-                println!("\nTHE {} IS STUCK AND CAN'T ATTACK", m_name);
-            }
+    /// Be attacked by the monsters
+    ///
+    /// Returns true if the player got knocked into another room.
+    fn combat_be_attacked(&mut self) -> bool {
+        let names = self.fighting_names();
+
+        let events = match self.game.be_attacked() {
+            Ok(events) => events,
+            Err(err) => panic!("error in combat being attacked {:#?}", err),
+        };
+
+        let mut knocked = false;
+
+        for event in events {
+            match event {
+                CombatEvent::MonsterWebbed(i) => {
+                    println!("\nTHE {} IS STUCK AND CAN'T ATTACK", names[i].1);
+                }
+
+                CombatEvent::MonsterHit(i, _damage, _defeated, armor_destroyed, web_broke) => {
+                    if web_broke {
+                        println!("\nTHE WEB JUST BROKE!");
+                    }
+
+                    println!("\nTHE {} ATTACKS", names[i].1);
 
-            Ok(CombatEvent::MonsterHit(_damage, _defeated, armor_destroyed, web_broke)) => {
-                if web_broke {
-                    println!("\nTHE WEB JUST BROKE!");
+                    println!("\n  OUCH! HE HIT YOU");
+
+                    if armor_destroyed {
+                        println!("\nYOUR ARMOR IS DESTROYED - GOOD LUCK\n");
+                    }
                 }
 
-                println!("\nTHE {} ATTACKS", m_name);
+                CombatEvent::MonsterMiss(i) => {
+                    println!("\nTHE {} ATTACKS", names[i].1);
 
-                println!("\n  OUCH! HE HIT YOU");
+                    println!("\n  HAH! HE MISSED YOU");
+                }
 
-                if armor_destroyed {
-                    println!("\nYOUR ARMOR IS DESTROYED - GOOD LUCK\n");
+                CombatEvent::MonsterRegenerate(i, _) => {
+                    println!("\nTHE {}'S WOUNDS CLOSE BEFORE YOUR EYES", names[i].1);
                 }
-            }
 
-            Ok(CombatEvent::MonsterMiss) => {
-                println!("\nTHE {} ATTACKS", m_name);
+                CombatEvent::MonsterAttacksAgain(i) => {
+                    println!("\nTHE {} LUNGES AGAIN!", names[i].1);
+                }
 
-                println!("\n  HAH! HE MISSED YOU");
-            }
+                CombatEvent::MonsterBreath(i, damage, _) => {
+                    println!("\nTHE {} BREATHES FIRE!", names[i].1);
 
-            Ok(any) => panic!("unexpected event while being attacked {:#?}", any),
+                    println!(
+                        "\n  IT BURNS RIGHT THROUGH YOUR ARMOR FOR {} DAMAGE",
+                        damage
+                    );
+                }
 
-            Err(err) => panic!("error in combat being attacked {:#?}", err),
+                CombatEvent::MonsterPoison(_, _) => {
+                    println!("\n  ITS BITE WAS POISONED!");
+                }
+
+                CombatEvent::MonsterBurnFlares(_, burned) => {
+                    if burned == 1 {
+                        println!("\n  ITS FLAMES BURN UP A FLARE");
+                    } else {
+                        println!("\n  ITS FLAMES BURN UP {} FLARES", burned);
+                    }
+                }
+
+                CombatEvent::MonsterStealGold(_, stolen) => {
+                    println!("\n  HE GRABS {} GP's FROM YOUR PURSE!", stolen);
+                }
+
+                CombatEvent::MonsterCharge(i, _, defeated) => {
+                    println!("\nTHE {} CHARGES!", names[i].1);
+
+                    println!("\n  OUCH! HE HIT YOU");
+
+                    if !defeated {
+                        println!("\nYOU ARE THROWN INTO THE NEXT ROOM");
+                        knocked = true;
+                    }
+                }
+
+                CombatEvent::MonsterFled(i, dir) => {
+                    println!(
+                        "\nTHE {} TURNS TAIL AND FLEES {}!",
+                        names[i].1,
+                        UI::direction_name(dir)
+                    );
+                }
+
+                any => panic!("unexpected event while being attacked {:#?}", any),
+            }
         }
+
+        knocked
     }
 
     /// Retreat
@@ -774,39 +1179,36 @@ impl UI {
     fn combat_retreat_dir(&mut self) {
         println!("\n\nYOU HAVE ESCAPED\n");
 
-        let dir;
-
         loop {
             let dir_str = UI::get_input(Some("\nDO YOU GO NORTH, SOUTH, EAST, OR WEST? "));
 
-            match dir_str.get(..1) {
-                Some("N") => {
-                    dir = Direction::North;
-                    break;
-                }
-                Some("S") => {
-                    dir = Direction::South;
-                    break;
-                }
-                Some("W") => {
-                    dir = Direction::West;
-                    break;
-                }
-                Some("E") => {
-                    dir = Direction::East;
-                    break;
-                }
+            let dir = match dir_str.get(..1) {
+                Some("N") => Direction::North,
+                Some("S") => Direction::South,
+                Some("W") => Direction::West,
+                Some("E") => Direction::East,
                 _ => {
                     println!("\n** DON'T PRESS YOUR LUCK {}", self.race_str());
+                    continue;
+                }
+            };
+
+            match self.game.retreat_dir(dir) {
+                Ok(_) => break,
+                Err(err) => {
+                    println!();
+                    self.blocked(err);
                 }
             }
         }
-
-        self.game.retreat_dir(dir);
     }
 
     /// Handle Bribe
     fn combat_bribe(&mut self) -> bool {
+        if self.game.difficulty().choose_bribe() {
+            return self.combat_bribe_offer();
+        }
+
         let mut bribed = false;
 
         match self.game.bribe_proposition() {
@@ -855,54 +1257,162 @@ impl UI {
         bribed
     }
 
-    /// Handle combat spells
-    fn combat_spell(&mut self, m_art: &str, m_name: &str) {
-        match UI::get_input(Some("\nWHICH SPELL (WEB, FIREBALL, OR DEATHSPELL)? ")).get(..1) {
-            Some("W") => match self.game.spell_web() {
-                Ok(CombatEvent::Hit(_)) => (),
-                Ok(CombatEvent::Died) => (),
-                Ok(any) => panic!("Unexpected: {:#?}", any),
-                Err(err) => panic!("{:#?}", err),
-            },
-            Some("F") => match self.game.spell_fireball() {
-                Ok(CombatEvent::Hit(hr)) => {
-                    println!("\n  IT DOES {} POINTS OF DAMAGE.\n", hr.damage);
-                    self.monster_defeated_message(hr, m_art, m_name);
-                }
-                Ok(CombatEvent::Died) => (),
-                Ok(any) => panic!("Unexpected: {:#?}", any),
-                Err(err) => panic!("{:#?}", err),
-            },
-            Some("D") => {
-                print!("\nDEATH - - - ");
-                match self.game.spell_deathspell() {
-                    Ok(CombatEvent::Hit(hr)) => {
-                        println!("HIS\n");
-                        self.monster_defeated_message(hr, m_art, m_name);
+    /// Let the player choose a treasure or some gold to bribe with
+    ///
+    /// Returns false without offering anything if the player thinks better of
+    /// it, so they can choose something else to do.
+    fn combat_bribe_offer(&mut self) -> bool {
+        let treasures = self.game.player_get_treasures();
+        let gp = self.game.player_gp();
+
+        let offer = if treasures.is_empty() && gp == 0 {
+            // Nothing is still an offer, just not a good one
+            BribeOffer::Gold(0)
+        } else {
+            println!("\nYOU COULD OFFER:\n");
+
+            for (i, t) in treasures.iter().enumerate() {
+                println!("  {}. {}", i + 1, UI::treasure_name(*t));
+            }
+
+            if gp > 0 {
+                println!("  G. SOME OF YOUR {} GOLD PIECES", gp);
+            }
+
+            println!("  N. NOTHING AFTER ALL");
+
+            loop {
+                let choice = UI::get_input(Some("\nWHAT WILL YOU OFFER? "));
+
+                if choice.starts_with('N') {
+                    return false;
+                } else if gp > 0 && choice.starts_with('G') {
+                    match UI::get_input(Some("HOW MUCH GOLD? ")).parse::<u32>() {
+                        Ok(amount) if amount <= gp => break BribeOffer::Gold(amount),
+                        _ => println!("\n** YOU HAVE {} GOLD PIECES", gp),
                     }
-                    Ok(CombatEvent::Died) => {
-                        println!("YOURS\n");
+                } else {
+                    match choice.parse::<usize>() {
+                        Ok(n) if n > 0 && n <= treasures.len() => {
+                            break BribeOffer::Treasure(treasures[n - 1]);
+                        }
+                        _ => println!("\n** CHOOSE ONE OF THE THINGS LISTED"),
                     }
-                    Ok(any) => panic!("Unexpected: {:#?}", any),
-                    Err(err) => panic!("{:#?}", err),
                 }
             }
-            _ => println!("\n** TRY ONE OF THE OPTIONS GIVEN"),
+        };
+
+        match self.game.bribe_offer(offer) {
+            Ok(true) => true,
+            Ok(false) => {
+                match offer {
+                    BribeOffer::Gold(0) => println!("\n'ALL I WANT IS YOUR LIFE!'"),
+                    _ => println!("\n'YOU CALL THAT A BRIBE?'"),
+                }
+
+                false
+            }
+            Err(err) => panic!("bribe offer: {:#?}", err),
         }
     }
 
-    /// Handle combat
-    fn combat(&mut self, monster_type: MonsterType) -> bool {
-        let m_name = UI::monster_name(monster_type);
-        let m_art = UI::get_article(&m_name);
+    /// Handle combat spells
+    fn combat_spell(&mut self) {
+        let names = self.fighting_names();
+
+        match UI::get_input(Some("\nWHICH SPELL (WEB, FIREBALL, OR DEATHSPELL)? ")).get(..1) {
+            Some("W") => {
+                if !self.combat_target() {
+                    return;
+                }
+
+                match self.game.spell_web() {
+                    Ok(CombatEvent::Hit(_)) => (),
+                    Ok(CombatEvent::Died) => (),
+                    Ok(any) => panic!("Unexpected: {:#?}", any),
+                    Err(err) => panic!("{:#?}", err),
+                }
+            }
+            Some("F") => match self.game.spell_fireball() {
+                Ok(events) => {
+                    for event in events {
+                        match event {
+                            CombatEvent::Hit(hr) => {
+                                let (m_art, m_name) = &names[hr.monster];
+
+                                if names.len() > 1 {
+                                    println!(
+                                        "\n  IT DOES {} POINTS OF DAMAGE TO THE {}.\n",
+                                        hr.damage, m_name
+                                    );
+                                } else {
+                                    println!("\n  IT DOES {} POINTS OF DAMAGE.\n", hr.damage);
+                                }
+
+                                self.monster_defeated_message(hr, m_art, m_name);
+                            }
+                            CombatEvent::Died => (),
+                            any => panic!("Unexpected: {:#?}", any),
+                        }
+                    }
+                }
+                Err(err) => panic!("{:#?}", err),
+            },
+            Some("D") => {
+                if !self.combat_target() {
+                    return;
+                }
 
+                // The target might have changed
+                let (m_art, m_name) = &names[self.game.target()];
+
+                print!("\nDEATH - - - ");
+                match self.game.spell_deathspell() {
+                    Ok(CombatEvent::Hit(hr)) => {
+                        println!("HIS\n");
+                        self.monster_defeated_message(hr, m_art, m_name);
+                    }
+                    Ok(CombatEvent::Died) => {
+                        println!("YOURS\n");
+                    }
+                    Ok(any) => panic!("Unexpected: {:#?}", any),
+                    Err(err) => panic!("{:#?}", err),
+                }
+            }
+            _ => println!("\n** TRY ONE OF THE OPTIONS GIVEN"),
+        }
+    }
+
+    /// Handle combat
+    fn combat(&mut self) -> bool {
         let mut in_combat = true;
         let mut retreated = false;
 
         while in_combat {
             match self.game.state() {
                 GameState::PlayerAttack => {
-                    println!("\nYOU'RE FACING {} {}!", m_art, m_name);
+                    let names = self.fighting_names();
+
+                    if names.len() > 1 {
+                        println!("\nYOU'RE FACING:\n");
+
+                        for (i, m) in self.game.fighting().iter().enumerate() {
+                            let name = UI::monster_name(m.monster_type());
+
+                            if *m.webbed() > 0 {
+                                println!(
+                                    "  {}. {} {} (WEBBED)",
+                                    i + 1,
+                                    UI::get_article(&name),
+                                    name
+                                );
+                            } else {
+                                println!("  {}. {} {}", i + 1, UI::get_article(&name), name);
+                            }
+                        }
+                    } else {
+                        println!("\nYOU'RE FACING {} {}!", names[0].0, names[0].1);
+                    }
 
                     print!("\nYOU MAY ATTACK OR RETREAT");
 
@@ -928,7 +1438,7 @@ impl UI {
                     let err_str = "\n** CHOOSE ONE OF THE OPTIONS LISTED.";
 
                     match UI::get_input(Some("YOUR CHOICE? ")).get(..1) {
-                        Some("A") => self.combat_attack(&m_art, &m_name),
+                        Some("A") => self.combat_attack(),
                         Some("R") => self.combat_retreat(),
                         Some("B") => {
                             if can_bribe {
@@ -941,7 +1451,7 @@ impl UI {
                         }
                         Some("C") => {
                             if can_cast_spell {
-                                self.combat_spell(&m_art, &m_name);
+                                self.combat_spell();
                             } else {
                                 println!("\n** YOU CAN'T CAST A SPELL NOW");
                             }
@@ -951,7 +1461,9 @@ impl UI {
                 }
 
                 GameState::MonsterAttack => {
-                    self.combat_be_attacked(&m_name);
+                    if self.combat_be_attacked() {
+                        retreated = true;
+                    }
                 }
 
                 GameState::Retreat => {
@@ -982,13 +1494,8 @@ impl UI {
 
                 println!("A NOBLE EFFORT, OH FORMERLY LIVING {}\n", self.race_str());
 
-                print!("YOU DIED FROM A LACK OF ");
-                if self.game.player_stat(Stat::Strength) == 0 {
-                    println!("STRENGTH");
-                } else if self.game.player_stat(Stat::Intelligence) == 0 {
-                    println!("INTELLIGENCE");
-                } else if self.game.player_stat(Stat::Dexterity) == 0 {
-                    println!("DEXTERITY");
+                if let Some(cause) = self.game.death_cause() {
+                    println!("{}", UI::death_cause_name(cause));
                 }
 
                 println!("\nWHEN YOU DIED YOU HAD:\n");
@@ -1056,6 +1563,306 @@ impl UI {
 
         // Show turns
         println!("\nAND IT TOOK YOU {} TURNS!\n", *self.game.turn());
+
+        // Show difficulty
+        println!(
+            "DIFFICULTY: {}\n",
+            UI::difficulty_name(self.game.difficulty())
+        );
+
+        self.game_stats();
+
+        // Show score
+        println!("YOUR SCORE IS {}\n", self.game.score());
+
+        // Show the seed and generator so the castle can be played again. A
+        // castle from a file can only be played again from the file.
+        match (self.game.castle_file(), self.game.generator()) {
+            (Some(path), _) => println!("CASTLE FILE: {}\n", path),
+            (None, Some(generator)) => println!(
+                "CASTLE SEED: {}, GENERATOR: {}\n",
+                self.game.seed(),
+                generator
+            ),
+            (None, None) => println!("CASTLE SEED: {}\n", self.game.seed()),
+        }
+
+        if self.game.practice() {
+            println!(
+                "THIS WAS A PRACTICE GAME WITH {} UNDOS, SO IT WON'T BE RECORDED\n",
+                self.game.undo_count()
+            );
+        } else if self.debug {
+            println!("THIS WAS A DEBUG GAME, SO IT WON'T BE RECORDED\n");
+        }
+    }
+
+    /// True if this game goes in the high scores and history
+    ///
+    /// Practice games can be undone, and debug games could have been cheated.
+    fn recorded(&self) -> bool {
+        !self.game.practice() && !self.debug
+    }
+
+    fn death_cause_name(c: DeathCause) -> String {
+        match c {
+            DeathCause::Monster(m) => {
+                let mon_str = UI::monster_name(m);
+                format!(
+                    "YOU WERE SLAIN BY {} {}",
+                    UI::get_article(&mon_str),
+                    mon_str
+                )
+            }
+            DeathCause::ExplodingChest => String::from("YOU WERE BLOWN UP BY A CHEST"),
+            DeathCause::Fireball => String::from("YOUR FIREBALL TOOK THE LAST OF YOU WITH IT"),
+            DeathCause::Deathspell => String::from("YOUR DEATHSPELL BACKFIRED"),
+            DeathCause::Web => String::from("YOUR WEB SPELL DRAINED THE LAST OF YOUR STRENGTH"),
+            DeathCause::Pool => String::from("YOU DRANK FROM THE WRONG POOL"),
+            DeathCause::Orb => String::from("THE CRYSTAL ORB'S VISION CAME TRUE"),
+            DeathCause::PitTrap => String::from("YOU FELL TO YOUR DEATH IN A PIT"),
+            DeathCause::DartTrap => String::from("A POISON DART FINISHED YOU OFF"),
+        }
+    }
+
+    fn outcome_name(o: Outcome) -> String {
+        String::from(o.name())
+    }
+
+    /// Print the high score table
+    ///
+    /// This wasn't in the original game
+    fn high_scores() {
+        let path = match HighScoreTable::default_path() {
+            Some(path) => path,
+            None => {
+                println!("** I DON'T KNOW WHERE TO KEEP THE HIGH SCORES");
+                return;
+            }
+        };
+
+        let table = match HighScoreTable::load(&path) {
+            Ok(table) => table,
+            Err(err) => {
+                println!("** CAN'T READ THE HIGH SCORES: {}", err);
+                return;
+            }
+        };
+
+        println!("{:-^64}\n", " HIGH SCORES ");
+
+        if table.scores().is_empty() {
+            println!("NOBODY HAS PLAYED YET\n");
+            return;
+        }
+
+        for (i, s) in table.scores().iter().enumerate() {
+            println!(
+                "{:>2}. {:>6} {:<12} {:<6} {:<6} {:<7} {:>5} TURNS {}",
+                i + 1,
+                s.score,
+                s.name,
+                UI::race_name(s.race),
+                UI::gender_name(s.gender),
+                UI::outcome_name(s.outcome),
+                s.turns,
+                s.date
+            );
+        }
+
+        println!();
+    }
+
+    /// Print the history of past games
+    ///
+    /// This wasn't in the original game
+    fn history(wins_only: bool, race: Option<Race>) {
+        let path = match History::default_path() {
+            Some(path) => path,
+            None => {
+                println!("** I DON'T KNOW WHERE TO KEEP THE HISTORY");
+                return;
+            }
+        };
+
+        let records = match History::load(&path) {
+            Ok(records) => records,
+            Err(err) => {
+                println!("** CAN'T READ THE HISTORY: {}", err);
+                return;
+            }
+        };
+
+        println!("{:-^64}\n", " HALL OF FAME ");
+
+        let mut count = 0;
+
+        for r in records.iter().rev() {
+            if wins_only && r.outcome != Outcome::Won {
+                continue;
+            }
+
+            if race.is_some() && race != Some(r.race) {
+                continue;
+            }
+
+            let castle = match (&r.castle, &r.generator) {
+                (Some(path), _) => format!("CASTLE {}", path),
+                (None, Some(generator)) => format!("SEED {} {}", r.seed, generator),
+                (None, None) => format!("SEED {}", r.seed),
+            };
+
+            println!(
+                "{} {} {} {} {} SCORE {}",
+                r.date,
+                UI::difficulty_name(r.difficulty),
+                UI::gender_name(r.gender),
+                UI::race_name(r.race),
+                castle,
+                r.score
+            );
+            println!("  {}\n", r.epitaph());
+
+            count += 1;
+        }
+
+        if count == 0 {
+            println!("NO GAMES TO SHOW\n");
+        }
+    }
+
+    /// Add this game to the history and show its epitaph
+    fn record_history(&self) {
+        let record = match RunRecord::from_game(&self.game) {
+            Some(record) => record,
+            None => return,
+        };
+
+        println!("{}\n", record.epitaph());
+
+        if !self.recorded() {
+            return;
+        }
+
+        if let Some(path) = History::default_path() {
+            if let Err(err) = History::append(&path, &record) {
+                println!("** CAN'T SAVE THE HISTORY: {}", err);
+            }
+        }
+    }
+
+    /// Add the player's score to the high score table if it's good enough
+    fn record_score(&self) {
+        if !self.recorded() {
+            return;
+        }
+
+        let outcome = match self.game.outcome() {
+            Some(outcome) => outcome,
+            None => return,
+        };
+
+        let path = match HighScoreTable::default_path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let mut table = match HighScoreTable::load(&path) {
+            Ok(table) => table,
+            Err(err) => {
+                println!("** CAN'T READ THE HIGH SCORES: {}", err);
+                return;
+            }
+        };
+
+        let score = self.game.score();
+
+        if !table.qualifies(score) {
+            return;
+        }
+
+        let name = loop {
+            let name = UI::get_input(Some("YOU MADE THE HIGH SCORES! WHAT IS YOUR NAME? "));
+
+            if !name.is_empty() {
+                break name;
+            }
+        };
+
+        table.add(HighScore {
+            name,
+            race: *self.game.player_race(),
+            gender: *self.game.player_gender(),
+            outcome,
+            score,
+            turns: *self.game.turn(),
+            date: highscore::today(),
+        });
+
+        if let Err(err) = table.save(&path) {
+            println!("** CAN'T SAVE THE HIGH SCORES: {}", err);
+            return;
+        }
+
+        println!();
+
+        UI::high_scores();
+    }
+
+    /// Print out the game statistics
+    ///
+    /// This wasn't in the original game
+    fn game_stats(&self) {
+        let stats = self.game.stats();
+
+        println!("{:-^64}\n", " STATISTICS ");
+
+        println!("ROOMS DISCOVERED: {}", stats.rooms_discovered);
+
+        println!("MONSTERS KILLED: {}", stats.total_kills());
+
+        let monsters = [
+            MonsterType::Kobold,
+            MonsterType::Orc,
+            MonsterType::Wolf,
+            MonsterType::Goblin,
+            MonsterType::Ogre,
+            MonsterType::Troll,
+            MonsterType::Bear,
+            MonsterType::Minotaur,
+            MonsterType::Gargoyle,
+            MonsterType::Chimera,
+            MonsterType::Balrog,
+            MonsterType::Dragon,
+            MonsterType::Vendor,
+        ];
+
+        for m in monsters.iter() {
+            let kills = stats.kills(*m);
+
+            if kills > 0 {
+                println!("  {} {}", kills, UI::monster_name(*m));
+            }
+        }
+
+        println!(
+            "DAMAGE DEALT: {}  DAMAGE TAKEN: {}",
+            stats.damage_dealt, stats.damage_taken
+        );
+        println!("SPELLS CAST: {}", stats.spells_cast);
+        println!(
+            "GP's FOUND: {}  SPENT: {}  FROM VENDORS: {}",
+            stats.gold_found, stats.gold_spent, stats.gold_from_vendors
+        );
+        println!(
+            "TREASURES COLLECTED: {}  BRIBED AWAY: {}",
+            stats.treasures_collected, stats.treasures_bribed
+        );
+        println!(
+            "POOLS DRUNK: {}  BOOKS OPENED: {}  CHESTS OPENED: {}",
+            stats.pools_drunk, stats.books_opened, stats.chests_opened
+        );
+        println!("SINKHOLES: {}  WARPS: {}\n", stats.sinkholes, stats.warps);
     }
 
     /// Ask the user if they want to play again
@@ -1089,21 +1896,22 @@ impl UI {
 
         println!();
 
-        let price_hash;
+        let price_hash = match self.game.vendor_treasure_offer() {
+            Ok(hash) => hash,
+            Err(err) => panic!("{:#?}", err),
+        };
 
-        match self.game.vendor_treasure_offer() {
-            Ok(hash) => price_hash = hash,
-            Err(err) => panic!(err),
+        if price_hash.is_empty() {
+            println!("THE VENDOR CAN'T AFFORD ANY MORE TREASURES\n");
+            return;
         }
 
         for t in treasures {
-            let price;
-
-            if let Some(p) = price_hash.get(&t) {
-                price = p;
-            } else {
-                panic!("price missing from hash");
-            }
+            // The vendor might not be able to pay anything for some
+            let price = match price_hash.get(&t) {
+                Some(p) => p,
+                None => continue,
+            };
 
             loop {
                 let yn = UI::get_input(Some(&format!(
@@ -1133,15 +1941,19 @@ impl UI {
 
     /// Trade armor
     fn vendor_trade_armor(&mut self) {
-        let leather_cost = Armor::cost(ArmorType::Leather, true);
-
-        if self.game.player_gp() < leather_cost {
+        let for_sale: Vec<(ArmorType, u32)> =
+            [ArmorType::Leather, ArmorType::Chainmail, ArmorType::Plate]
+                .iter()
+                .map(|a| (*a, self.game.armor_cost(*a, true)))
+                .filter(|(a, cost)| {
+                    self.game.vendor_stock(Goods::Armor(*a)) > 0 && self.game.player_gp() >= *cost
+                })
+                .collect();
+
+        if for_sale.is_empty() {
             return;
         }
 
-        let chainmail_cost = Armor::cost(ArmorType::Chainmail, true);
-        let plate_cost = Armor::cost(ArmorType::Plate, true);
-
         println!(
             "\nOK, {}, YOU HAVE {} GOLD PIECES AND {}",
             self.race_str(),
@@ -1151,14 +1963,10 @@ impl UI {
 
         println!("\nHERE IS A LIST OF ARMOR YOU CAN BUY");
 
-        print!("\nNOTHING<0> LEATHER<{}>", leather_cost);
+        print!("\nNOTHING<0>");
 
-        if self.game.player_gp() >= chainmail_cost {
-            print!(" CHAINMAIL<{}>", chainmail_cost)
-        }
-
-        if self.game.player_gp() >= plate_cost {
-            print!(" PLATE<{}>", plate_cost)
+        for (a, cost) in for_sale {
+            print!(" {}<{}>", UI::armor_name(a), cost);
         }
 
         println!();
@@ -1170,18 +1978,21 @@ impl UI {
                 Some("P") => match self.game.player_purchase_armor(ArmorType::Plate, true) {
                     Ok(_) => break,
                     Err(Error::NotEnoughGP) => println!("\n** YOU CAN'T AFFORD PLATE"),
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF PLATE"),
                     _ => (),
                 },
                 Some("C") => match self.game.player_purchase_armor(ArmorType::Chainmail, true) {
                     Ok(_) => break,
                     Err(Error::NotEnoughGP) => println!("\n** YOU HAVEN'T GOT THAT MUCH CASH"),
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF CHAINMAIL"),
+                    _ => (),
+                },
+                Some("L") => match self.game.player_purchase_armor(ArmorType::Leather, true) {
+                    Ok(_) => break,
+                    Err(Error::NotEnoughGP) => println!("\n** YOU HAVEN'T GOT THAT MUCH CASH"),
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF LEATHER"),
                     _ => (),
                 },
-                Some("L") => {
-                    // If we get to this point we already had enough to buy leather
-                    let _ = self.game.player_purchase_armor(ArmorType::Leather, true);
-                    break;
-                }
                 Some("N") => break,
                 _ => {
                     println!("\n** DON'T BE SILLY. CHOOSE A SELECTION");
@@ -1192,15 +2003,19 @@ impl UI {
 
     /// Trade armor
     fn vendor_trade_weapons(&mut self) {
-        let dagger_cost = Weapon::cost(WeaponType::Dagger, true);
-
-        if self.game.player_gp() < dagger_cost {
+        let for_sale: Vec<(WeaponType, u32)> =
+            [WeaponType::Dagger, WeaponType::Mace, WeaponType::Sword]
+                .iter()
+                .map(|w| (*w, self.game.weapon_cost(*w, true)))
+                .filter(|(w, cost)| {
+                    self.game.vendor_stock(Goods::Weapon(*w)) > 0 && self.game.player_gp() >= *cost
+                })
+                .collect();
+
+        if for_sale.is_empty() {
             return;
         }
 
-        let mace_cost = Weapon::cost(WeaponType::Mace, true);
-        let sword_cost = Weapon::cost(WeaponType::Sword, true);
-
         println!(
             "\nYOU HAVE {} GP's LEFT WITH {} IN HAND",
             self.game.player_gp(),
@@ -1209,14 +2024,10 @@ impl UI {
 
         println!("\nHERE IS A LIST OF ARMOR YOU CAN BUY");
 
-        print!("\nNOTHING<0> DAGGER<{}>", dagger_cost);
-
-        if self.game.player_gp() >= mace_cost {
-            print!(" MACE<{}>", mace_cost)
-        }
+        print!("\nNOTHING<0>");
 
-        if self.game.player_gp() >= sword_cost {
-            print!(" SWORD<{}>", sword_cost)
+        for (w, cost) in for_sale {
+            print!(" {}<{}>", UI::weapon_name(w), cost);
         }
 
         println!();
@@ -1230,18 +2041,21 @@ impl UI {
                     Err(Error::NotEnoughGP) => {
                         println!("\n** DUNGEON EXPRESS CARD - YOU LEFT HOME WITHOUT IT!")
                     }
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF SWORDS"),
                     _ => (),
                 },
                 Some("M") => match self.game.player_purchase_weapon(WeaponType::Mace, true) {
                     Ok(_) => break,
                     Err(Error::NotEnoughGP) => println!("\n** SORRY SIR, I DON'T GIVE CREDIT"),
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF MACES"),
+                    _ => (),
+                },
+                Some("D") => match self.game.player_purchase_weapon(WeaponType::Dagger, true) {
+                    Ok(_) => break,
+                    Err(Error::NotEnoughGP) => println!("\n** SORRY SIR, I DON'T GIVE CREDIT"),
+                    Err(Error::OutOfStock) => println!("\n** I'M FRESH OUT OF DAGGERS"),
                     _ => (),
                 },
-                Some("D") => {
-                    // If we get to this &point we already had enough to buy a dagger
-                    let _ = self.game.player_purchase_weapon(WeaponType::Dagger, true);
-                    break;
-                }
                 Some("N") => break,
                 _ => {
                     println!("\n** TRY CHOOSING A SELECTION");
@@ -1250,6 +2064,114 @@ impl UI {
         }
     }
 
+    /// Have a Vendor repair a worn weapon
+    ///
+    /// This wasn't in the original game.
+    fn vendor_repair_weapon(&mut self) {
+        if !self.game.player_weapon_worn() || !self.game.vendor_can_afford_weapon_repair() {
+            return;
+        }
+
+        let w_name = UI::weapon_name(self.game.player_weapon_type());
+
+        if UI::vendor_offer(&format!(
+            "\nWANT YOUR {} REPAIRED FOR {} GP's? ",
+            w_name,
+            self.game.vendor_weapon_repair_cost()
+        )) {
+            match self.game.vendor_repair_weapon() {
+                Ok(_) => println!("\nGOOD AS NEW!"),
+                Err(err) => panic!("{:#?}", err),
+            }
+        }
+    }
+
+    /// Have a Vendor repair damaged armor
+    ///
+    /// This wasn't in the original game.
+    fn vendor_repair_armor(&mut self) {
+        if !self.game.player_armor_damaged()
+            || self.game.player_gp() < self.game.vendor_armor_repair_cost()
+        {
+            return;
+        }
+
+        let a_name = UI::armor_name(self.game.player_armor_type());
+
+        if UI::vendor_offer(&format!(
+            "\nWANT YOUR {} REPAIRED FOR {} GP's? ",
+            a_name,
+            self.game.vendor_armor_repair_cost()
+        )) {
+            match self.game.vendor_repair_armor() {
+                Ok(_) => println!("\nGOOD AS NEW!"),
+                Err(err) => panic!("{:#?}", err),
+            }
+        }
+    }
+
+    /// Have a Vendor cure what ails the player
+    ///
+    /// Vendors can lift a curse, cure blindness, and get a book unstuck. This
+    /// wasn't in the original game.
+    fn vendor_cures(&mut self) {
+        if let Some(curse) = self.game.vendor_identify_curse() {
+            println!(
+                "\nTHE VENDOR LOOKS YOU OVER. \"YOU'RE UNDER THE CURSE OF {}\"",
+                curse.name()
+            );
+
+            let cost = self.game.vendor_curse_removal_cost();
+
+            if self.game.player_gp() >= cost
+                && UI::vendor_offer(&format!("\nWANT IT LIFTED FOR {} GP's? ", cost))
+            {
+                match self.game.vendor_remove_curse() {
+                    Ok(_) => println!("\nYOU FEEL A WEIGHT LIFT FROM YOUR SHOULDERS"),
+                    Err(err) => panic!("{:#?}", err),
+                }
+            }
+        }
+
+        let cost = self.game.vendor_cure_blindness_cost();
+
+        if self.game.player_is_blind()
+            && self.game.player_gp() >= cost
+            && UI::vendor_offer(&format!("\nWANT YOUR SIGHT BACK FOR {} GP's? ", cost))
+        {
+            match self.game.vendor_cure_blindness() {
+                Ok(_) => println!("\nYOU CAN SEE AGAIN!"),
+                Err(err) => panic!("{:#?}", err),
+            }
+        }
+
+        let cost = self.game.vendor_unstick_book_cost();
+
+        if self.game.player_book_stuck()
+            && self.game.player_gp() >= cost
+            && UI::vendor_offer(&format!(
+                "\nWANT THAT BOOK OFF YOUR HANDS FOR {} GP's? ",
+                cost
+            ))
+        {
+            match self.game.vendor_unstick_book() {
+                Ok(_) => println!("\nTHE BOOK COMES AWAY WITH A SUCKING SOUND"),
+                Err(err) => panic!("{:#?}", err),
+            }
+        }
+    }
+
+    /// Ask if the player wants something from a Vendor
+    fn vendor_offer(prompt: &str) -> bool {
+        loop {
+            match UI::get_input(Some(prompt)).get(..1) {
+                Some("Y") => break true,
+                Some("N") => break false,
+                _ => println!("\n** ANSWER YES OR NO"),
+            }
+        }
+    }
+
     /// Buy stats from a Vendor
     fn vendor_buy_stats(&mut self) {
         let stats = [Stat::Strength, Stat::Intelligence, Stat::Dexterity];
@@ -1263,12 +2185,18 @@ impl UI {
                 break;
             }
 
+            if self.game.vendor_stock(Goods::Potion(*s)) == 0 {
+                i += 1;
+                continue;
+            }
+
             let stat_name = UI::stat_name(*s);
 
             loop {
                 let play_again = UI::get_input(Some(&format!(
-                    "\nWANT TO BUY A POTION OF {} FOR 1000 GP's? ",
-                    stat_name
+                    "\nWANT TO BUY A POTION OF {} FOR {} GP's? ",
+                    stat_name,
+                    self.game.vendor_stat_cost()
                 )));
 
                 match play_again.get(..1) {
@@ -1295,12 +2223,18 @@ impl UI {
 
     /// Buy a lamp from the vendor
     fn vendor_buy_lamp(&mut self) {
-        if self.game.player_has_lamp() || !self.game.vendor_can_afford_lamp() {
+        if self.game.player_has_lamp()
+            || !self.game.vendor_can_afford_lamp()
+            || self.game.vendor_stock(Goods::Lamp) == 0
+        {
             return;
         }
 
         loop {
-            let lamp = UI::get_input(Some("\nWANT A LAMP FOR OR 1000 GP's? "));
+            let lamp = UI::get_input(Some(&format!(
+                "\nWANT A LAMP FOR OR {} GP's? ",
+                self.game.vendor_lamp_cost()
+            )));
 
             match lamp.get(..1) {
                 Some("Y") => {
@@ -1324,15 +2258,23 @@ impl UI {
     fn vendor_trade(&mut self) {
         self.vendor_trade_treasures();
 
-        if self.game.player_gp() < 1000 {
+        if !self.game.vendor_will_sell() {
+            println!("\nTHE VENDOR SAYS HE'S HEARD ABOUT YOU, AND WON'T SELL YOU A THING");
+            return;
+        }
+
+        if !self.game.vendor_can_afford_anything() {
             println!("\n** YOU'RE TOO POOR TO TRADE");
             return;
         }
 
         self.vendor_trade_armor();
+        self.vendor_repair_armor();
         self.vendor_trade_weapons();
+        self.vendor_repair_weapon();
         self.vendor_buy_stats();
         self.vendor_buy_lamp();
+        self.vendor_cures();
     }
 
     /// Interact with a Vendor
@@ -1379,31 +2321,41 @@ impl UI {
             return false;
         }
 
-        let dir;
-
         let dir_str = UI::get_input(Some("WHERE DO YOU WANT TO SHINE THE LAMP (N,S,E, OR W)? "));
 
-        match dir_str.get(..1) {
-            Some("N") => dir = Direction::North,
-            Some("S") => dir = Direction::South,
-            Some("W") => dir = Direction::West,
-            Some("E") => dir = Direction::East,
+        let dir = match dir_str.get(..1) {
+            Some("N") => Direction::North,
+            Some("S") => Direction::South,
+            Some("W") => Direction::West,
+            Some("E") => Direction::East,
             _ => {
                 println!("\n** TURKEY! THAT'S NOT A DIRECTION");
                 return false;
             }
-        }
+        };
 
-        let (x, y, z, room_type);
+        let (x, y, z, edge);
 
         match self.game.shine_lamp(dir) {
-            Ok((tx, ty, tz, troom_type)) => {
+            Ok((tx, ty, tz, _, tedge)) => {
                 x = tx;
                 y = ty;
                 z = tz;
-                room_type = troom_type;
+                edge = tedge;
+            }
+            Err(Error::Wall) => {
+                println!("\nTHE LAMP SHINES ON A WALL");
+                return true;
             }
-            Err(err) => panic!(err),
+            Err(Error::Locked) => {
+                println!("\nTHE LAMP SHINES ON A LOCKED DOOR");
+                return true;
+            }
+            Err(err) => panic!("{:#?}", err),
+        }
+
+        if edge == Edge::SecretDoor {
+            println!("\nTHE LAMP REVEALS A SECRET DOOR!");
         }
 
         println!(
@@ -1413,7 +2365,7 @@ impl UI {
             z + 1
         );
 
-        let room_str = UI::room_name(&room_type);
+        let room_str = UI::contents_name(self.game.dungeon_room_at(x, y, z));
 
         println!("THERE YOU'LL FIND {}", room_str);
 
@@ -1433,7 +2385,7 @@ impl UI {
         }
 
         if let Err(err) = self.game.flare() {
-            panic!(err);
+            panic!("{:#?}", err);
         }
 
         let xm1 = self.game.player_x() as i32 - 1;
@@ -1444,23 +2396,31 @@ impl UI {
         for y in ym1..(ym1 + 3) {
             let yw = self.game.wrap_y(y);
 
+            let mut below = String::new();
+
             for x in xm1..(xm1 + 3) {
                 let xw = self.game.wrap_x(x);
 
-                let room_type = self.game.dungeon_room_at(xw, yw, z).room_type();
+                let room_type = self.game.dungeon_room_at(xw, yw, z).seen_type();
 
                 if x == xm1 {
                     print!(" ");
                 }
 
-                print!("{}", UI::room_char(&room_type));
+                print!("{}", UI::room_char(room_type));
 
                 if x == xm1 || x == xm1 + 1 {
-                    print!("     ");
+                    let edge = self.game.dungeon_edge(xw, yw, z, Direction::East);
+                    print!("  {}  ", UI::edge_char(edge, true));
+                }
+
+                if y < ym1 + 2 {
+                    let edge = self.game.dungeon_edge(xw, yw, z, Direction::South);
+                    below.push_str(&format!("{:>2}    ", UI::edge_char(edge, false)));
                 }
             }
 
-            println!("\n");
+            println!("\n{}", below.trim_end());
         }
 
         true
@@ -1528,9 +2488,10 @@ impl UI {
                 ChestEvent::Explode => println!("KABOOM! IT EXPLODES"),
                 ChestEvent::Gas => println!("GAS! YOU STAGGER FROM THE ROOM"),
                 ChestEvent::Treasure(amount) => println!("YOU FIND {} GOLD PIECES", amount),
+                ChestEvent::Key => println!("YOU FIND A KEY"),
             },
 
-            Err(err) => panic!(err),
+            Err(err) => panic!("{:#?}", err),
         }
 
         println!();
@@ -1553,7 +2514,7 @@ impl UI {
                     println!("THE BOOK STICKS TO YOUR HANDS -\n\nNOW YOU CAN'T DRAW YOUR WEAPON!")
                 }
             },
-            Err(err) => panic!(err),
+            Err(err) => panic!("{:#?}", err),
         }
 
         println!();
@@ -1561,15 +2522,16 @@ impl UI {
 
     /// Open a book or chest
     pub fn open(&mut self) -> bool {
-        let room_type = self.game.room_at_player().room_type().clone();
+        let room = self.game.room_at_player();
 
-        match room_type {
-            RoomType::Chest => self.open_chest(),
-            RoomType::Book => self.open_book(),
-            _ => {
-                println!("** THE ONLY THING YOU OPENED WAS YOUR BIG MOUTH");
-                return false;
-            }
+        // A chest gets opened before a book in the same room
+        if room.has(&RoomType::Chest) {
+            self.open_chest();
+        } else if room.has(&RoomType::Book) {
+            self.open_book();
+        } else {
+            println!("** THE ONLY THING YOU OPENED WAS YOUR BIG MOUTH");
+            return false;
         }
 
         true
@@ -1615,6 +2577,10 @@ impl UI {
 
         self.game.curse_effects();
 
+        if self.game.poison_effects() {
+            println!("\nTHE POISON BURNS IN YOUR VEINS");
+        }
+
         self.game.curse_check();
 
         self.rand_message();
@@ -1630,6 +2596,238 @@ impl UI {
         }
     }
 
+    /// Undo some number of turns in practice mode
+    ///
+    /// This wasn't in the original game
+    pub fn undo(&mut self, count: &str) -> bool {
+        let count = match count.trim() {
+            "" => 1,
+            n => match n.parse() {
+                Ok(n) => n,
+                Err(_) => {
+                    println!("** UNDO HOW MANY TURNS?");
+                    return false;
+                }
+            },
+        };
+
+        match self.game.undo(count) {
+            Ok(turns) => {
+                println!("TIME FLOWS BACKWARDS {} TURN(S)...", turns);
+
+                self.print_location();
+                self.print_stats();
+
+                true
+            }
+            Err(Error::NotPracticing) => {
+                println!("** YOU CAN ONLY UNDO IN PRACTICE MODE");
+                false
+            }
+            Err(Error::NothingToUndo) => {
+                println!("** THERE'S NOTHING TO UNDO");
+                false
+            }
+            Err(err) => panic!("{:#?}", err),
+        }
+    }
+
+    /// Offer to undo the player's death in practice mode
+    ///
+    /// Returns true if the player is alive again.
+    pub fn undo_death(&mut self) -> bool {
+        if !self.game.practice() {
+            return false;
+        }
+
+        loop {
+            match UI::get_input(Some("\nPRACTICE MODE: UNDO YOUR LAST MOVE? ")).get(..1) {
+                Some("Y") => {
+                    println!();
+                    break self.undo("");
+                }
+                Some("N") => break false,
+                _ => println!("\n** ANSWER YES OR NO"),
+            }
+        }
+    }
+
+    /// Parse the name of something that can be spawned in a room
+    fn debug_room_type(&self, name: &str) -> Option<RoomType> {
+        let room_type = match name {
+            "EMPTY" => RoomType::Empty,
+            "GOLD" => RoomType::Gold,
+            "POOL" => RoomType::Pool,
+            "CHEST" => RoomType::Chest,
+            "FLARES" => RoomType::Flares,
+            "WARP" => RoomType::Warp(false),
+            "SINKHOLE" => RoomType::Sinkhole,
+            "ORB" => RoomType::CrystalOrb,
+            "BOOK" => RoomType::Book,
+            "PIT" => RoomType::PitTrap,
+            "DART" => RoomType::DartTrap,
+            "ALARM" => RoomType::AlarmTrap,
+            "SLIME" => RoomType::SlimeTrap,
+            "VAULT" => RoomType::Vault(Box::new(RoomType::Treasure(Treasure::new(0)))),
+            _ => {
+                let monsters = [
+                    MonsterType::Kobold,
+                    MonsterType::Orc,
+                    MonsterType::Wolf,
+                    MonsterType::Goblin,
+                    MonsterType::Ogre,
+                    MonsterType::Troll,
+                    MonsterType::Bear,
+                    MonsterType::Minotaur,
+                    MonsterType::Gargoyle,
+                    MonsterType::Chimera,
+                    MonsterType::Balrog,
+                    MonsterType::Dragon,
+                    MonsterType::Vendor,
+                ];
+
+                for m in monsters {
+                    if UI::monster_name(m) == name {
+                        let monster = Monster::new(m, false, self.game.difficulty());
+                        return Some(RoomType::Monster(monster));
+                    }
+                }
+
+                for i in 0..TREASURE_COUNT {
+                    let treasure = Treasure::new(i);
+                    let t_name = UI::treasure_name(*treasure.treasure_type());
+
+                    if t_name == name || t_name == format!("THE {}", name) {
+                        return Some(RoomType::Treasure(treasure));
+                    }
+                }
+
+                return None;
+            }
+        };
+
+        Some(room_type)
+    }
+
+    /// Print the debug console commands
+    fn debug_help() {
+        println!("MAP               REVEAL THIS LEVEL");
+        println!("WARP X Y LEVEL    MOVE ANYWHERE");
+        println!("ST|IQ|DX N        SET A STAT");
+        println!("GP N              SET GOLD PIECES");
+        println!("KEYS N            SET KEYS");
+        println!("SPAWN THING       ADD A MONSTER OR ITEM TO THIS ROOM");
+        println!("CURSE NAME        TOGGLE A CURSE");
+        println!("WHERE             SHOW THE ORB OF ZOT AND RUNESTAFF");
+        println!("ORB | RUNESTAFF   TAKE THE ORB OF ZOT OR RUNESTAFF");
+        println!("EXIT              BACK TO THE GAME");
+    }
+
+    /// Run the debug console
+    ///
+    /// Returns true if the player's room changed and needs to be shown.
+    ///
+    /// This wasn't in the original game
+    pub fn debug_console(&mut self) -> bool {
+        let mut room_changed = false;
+
+        println!("DEBUG CONSOLE. TYPE HELP FOR COMMANDS.");
+
+        loop {
+            let line = UI::get_input(Some("\nDEBUG> "));
+            let args: Vec<&str> = line.split_whitespace().collect();
+
+            let num = |i: usize| args.get(i).and_then(|a| a.parse::<u32>().ok());
+
+            match args.first().copied() {
+                None | Some("EXIT") | Some("Q") => break,
+                Some("HELP") | Some("?") => UI::debug_help(),
+                Some("MAP") => self.map(true),
+                Some("WARP") => match (num(1), num(2), num(3)) {
+                    (Some(x), Some(y), Some(z)) if x > 0 && y > 0 && z > 0 => {
+                        match self.game.debug_warp(x - 1, y - 1, z - 1) {
+                            Ok(()) => {
+                                println!("OK");
+                                room_changed = true;
+                            }
+                            Err(Error::OutOfBounds) => println!("** THAT'S OUTSIDE THE CASTLE"),
+                            Err(err) => panic!("{:#?}", err),
+                        }
+                    }
+                    _ => println!("** WARP X Y LEVEL"),
+                },
+                Some(s @ "ST") | Some(s @ "IQ") | Some(s @ "DX") => {
+                    let stat = match s {
+                        "ST" => Stat::Strength,
+                        "IQ" => Stat::Intelligence,
+                        _ => Stat::Dexterity,
+                    };
+
+                    match num(1) {
+                        Some(n) if n > 0 => {
+                            let n = self.game.debug_set_stat(stat, n);
+                            println!("{} IS NOW {}", UI::stat_name(stat), n);
+                        }
+                        _ => println!("** {} 1-18", s),
+                    }
+                }
+                Some("GP") => match num(1) {
+                    Some(n) => {
+                        self.game.debug_set_gp(n);
+                        println!("YOU NOW HAVE {} GP's", n);
+                    }
+                    None => println!("** GP N"),
+                },
+                Some("KEYS") => match num(1) {
+                    Some(n) => {
+                        self.game.debug_set_keys(n);
+                        println!("YOU NOW HAVE {} KEYS", n);
+                    }
+                    None => println!("** KEYS N"),
+                },
+                Some("SPAWN") => match self.debug_room_type(&args[1..].join(" ")) {
+                    Some(room_type) => {
+                        println!("HERE YOU FIND {}", UI::room_name(&room_type));
+                        self.game.debug_add_to_room(room_type);
+                        room_changed = true;
+                    }
+                    None => println!("** I DON'T KNOW HOW TO SPAWN THAT"),
+                },
+                Some("CURSE") => {
+                    let name = args[1..].join(" ");
+
+                    let curse = CurseType::from_name(&name).filter(|c| *c != CurseType::None);
+
+                    match curse {
+                        Some(c) => {
+                            if self.game.debug_toggle_curse(c) {
+                                println!("YOU ARE CURSED WITH {}", name);
+                            } else {
+                                println!("YOU ARE FREE OF {}", name);
+                            }
+                        }
+                        None => println!("** FORGETFULNESS, THE LEECH, OR LETHARGY"),
+                    }
+                }
+                Some("WHERE") => {
+                    println!("ORB OF ZOT: {}", self.game.debug_orb_of_zot_location());
+                    println!("RUNESTAFF: {}", self.game.debug_runestaff_location());
+                }
+                Some("ORB") => {
+                    self.game.debug_give_orb_of_zot();
+                    println!("YOU NOW HAVE THE ORB OF ZOT");
+                }
+                Some("RUNESTAFF") => {
+                    self.game.debug_give_runestaff();
+                    println!("YOU NOW HAVE THE RUNESTAFF");
+                }
+                _ => println!("** TYPE HELP FOR COMMANDS"),
+            }
+        }
+
+        room_changed
+    }
+
     /// Quit the game
     pub fn quit(&mut self) -> bool {
         loop {
@@ -1658,21 +2856,268 @@ impl UI {
         println!("(W)EST       (M)AP         (O)PEN");
         println!("(E)AST       (L)AMP        (H)ELP");
         println!("(U)P         (F)LARE       (Q)UIT");
+
+        if self.game.practice() {
+            println!("\nUNDO [N] TAKES BACK THE LAST N TURNS");
+        }
+
+        if self.debug {
+            println!("\nDEBUG OPENS THE DEBUG CONSOLE");
+        }
+    }
+
+    /// Read and check a castle file, exiting if there's a problem
+    ///
+    /// This wasn't in the original game
+    fn load_castle(path: &str) -> String {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                std::process::exit(1);
+            }
+        };
+
+        if let Err(err) = castle::parse(&text, Difficulty::Classic) {
+            eprintln!("{}: {}", path, UI::castle_error(err));
+            std::process::exit(1);
+        }
+
+        text
+    }
+
+    /// Return the export function for a file name, based on its extension
+    fn export_format(path: &str) -> Option<fn(&Dungeon) -> String> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+
+        match ext.to_lowercase().as_str() {
+            "txt" => Some(export::to_text),
+            "json" => Some(export::to_json),
+            "svg" => Some(export::to_svg),
+            _ => None,
+        }
+    }
+
+    /// Write the castle out to a file
+    ///
+    /// This wasn't in the original game
+    ///
+    /// Each game after the first gets its own file, numbered like
+    /// `castle-2.txt`, and files that are already there are left alone unless
+    /// `force` is set.
+    fn export(game: &Game, path: &str, game_num: u32, force: bool) {
+        let to_format = UI::export_format(path).unwrap();
+
+        let path = UI::export_path(path, game_num);
+
+        if !force && std::path::Path::new(&path).exists() {
+            println!(
+                "** {} ALREADY EXISTS, SO THIS CASTLE WON'T BE SAVED\n",
+                path
+            );
+            return;
+        }
+
+        match std::fs::write(&path, to_format(game.dungeon())) {
+            Ok(()) => println!("THIS CASTLE IS SAVED IN {}\n", path),
+            Err(err) => eprintln!("{}: {}", path, err),
+        }
+    }
+
+    /// Return the file to export a game's castle to
+    ///
+    /// The first game uses the path as given, and later ones add their number
+    /// to the name.
+    fn export_path(path: &str, game_num: u32) -> String {
+        if game_num <= 1 {
+            return String::from(path);
+        }
+
+        let path = std::path::Path::new(path);
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+        path.with_file_name(format!("{}-{}.{}", stem, game_num, ext))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Describe a problem with a castle file
+    fn castle_error(err: Error) -> String {
+        match err {
+            Error::CastleSyntax(line) => format!("line {}: can't read this line", line),
+            Error::CastleEntrance => String::from("need one entrance, on level 1"),
+            Error::CastleOrbOfZot => String::from("need one orb of zot warp (Z)"),
+            Error::CastleRunestaff => String::from("need one runestaff monster (R:NAME)"),
+            Error::CastleStairs(level) => {
+                format!("level {}: stairs don't line up with the next level", level)
+            }
+            Error::CastleKeys => String::from("need a key monster (K:NAME) for each vault"),
+            any => format!("{:?}", any),
+        }
+    }
+}
+
+/// Command line options
+#[derive(Default)]
+struct Options {
+    scores: bool,
+    history: bool,
+    wins_only: bool,
+    race: Option<Race>,
+    seed: Option<u64>,
+    practice: bool,
+    debug: bool,
+    castle: Option<String>,
+    export: Option<String>,
+    force: bool,
+    generator: Option<Box<dyn DungeonGenerator>>,
+}
+
+/// Print command line usage and exit
+fn usage() -> ! {
+    eprintln!("usage: wizardscastle [--seed N] [--castle FILE] [--export FILE [--force]]");
+    eprintln!("                     [--practice]");
+    eprintln!("                     [--generator classic|vaults|depth|maze]");
+    eprintln!("                     [--debug]");
+    eprintln!("       wizardscastle --scores");
+    eprintln!("       wizardscastle --history [--wins] [--race RACE]");
+    std::process::exit(1);
+}
+
+/// Parse the command line
+fn parse_args() -> Options {
+    let mut options = Options::default();
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scores" => options.scores = true,
+            "--history" => options.history = true,
+            "--wins" => options.wins_only = true,
+            "--practice" => options.practice = true,
+            "--debug" => options.debug = true,
+            "--force" => options.force = true,
+            "--castle" => options.castle = Some(args.next().unwrap_or_else(|| usage())),
+            "--generator" => {
+                let name = args.next().unwrap_or_else(|| usage()).to_lowercase();
+
+                let generator: Box<dyn DungeonGenerator> = match name.as_str() {
+                    "classic" => Box::new(ClassicGenerator),
+                    "vaults" => Box::new(VaultGenerator),
+                    "depth" => Box::new(DepthGenerator::new(8)),
+                    "maze" => Box::new(MazeGenerator::new(Box::new(ClassicGenerator))),
+                    _ => usage(),
+                };
+
+                options.generator = Some(generator);
+            }
+            "--export" => {
+                let path = args.next().unwrap_or_else(|| usage());
+
+                if UI::export_format(&path).is_none() {
+                    eprintln!("{}: export to .txt, .json, or .svg", path);
+                    std::process::exit(1);
+                }
+
+                options.export = Some(path);
+            }
+            "--race" => {
+                let race = args.next().unwrap_or_else(|| usage()).to_uppercase();
+
+                options.race = match race.get(..1) {
+                    Some("H") => Some(Race::Hobbit),
+                    Some("E") => Some(Race::Elf),
+                    Some("M") => Some(Race::Human),
+                    Some("D") => Some(Race::Dwarf),
+                    _ => usage(),
+                };
+            }
+            "--seed" => {
+                let seed = args.next().unwrap_or_else(|| usage());
+
+                options.seed = Some(seed.parse().unwrap_or_else(|_| usage()));
+            }
+            _ => usage(),
+        }
+    }
+
+    // A castle file has its own layout, so no generator can make it
+    if options.castle.is_some() && options.generator.is_some() {
+        eprintln!("--castle and --generator can't be used together");
+        std::process::exit(1);
+    }
+
+    // Don't clobber a file that was already there
+    if let Some(path) = &options.export {
+        if !options.force && std::path::Path::new(path).exists() {
+            eprintln!("{}: already exists, use --force to replace it", path);
+            std::process::exit(1);
+        }
     }
+
+    options
 }
 
 /// Main
 fn main() {
+    let mut options = parse_args();
+
+    if options.scores {
+        UI::high_scores();
+        return;
+    }
+
+    if options.history {
+        UI::history(options.wins_only, options.race);
+        return;
+    }
+
+    // Read the castle file up front so mistakes show up right away
+    let castle_text = options.castle.as_ref().map(|path| UI::load_castle(path));
+
     let mut playing = true;
+    let mut game_num = 0;
 
     UI::intro();
 
     while playing {
-        let game = Game::new(8, 8, 8);
+        game_num += 1;
+
+        let difficulty = UI::difficulty_select();
+
+        // Only the first game uses the seed from the command line
+        let seed = options.seed.take().unwrap_or_else(rng::random_seed);
+
+        let mut game = match (&castle_text, &options.generator) {
+            (Some(text), _) => {
+                // Already checked, so this can't fail
+                let dungeon = castle::parse(text, difficulty).unwrap();
+
+                Game::from_dungeon(dungeon, difficulty, seed)
+            }
+            (None, Some(generator)) => {
+                Game::with_generator(generator.as_ref(), 8, 8, 8, difficulty, seed)
+            }
+            (None, None) => Game::with_seed(8, 8, 8, difficulty, seed),
+        };
+
+        game.set_practice(options.practice);
+
+        if let Some(path) = &options.castle {
+            game.set_castle_file(path);
+        }
+
+        if let Some(path) = &options.export {
+            UI::export(&game, path, game_num, options.force);
+        }
 
         let mut ui = UI {
             game,
             rng: thread_rng(),
+            debug: options.debug,
         };
 
         ui.equip();
@@ -1691,12 +3136,21 @@ fn main() {
         while alive {
             // See if we were killed by something
             if ui.game.state() == GameState::Dead {
-                alive = false;
-                continue;
+                if ui.undo_death() {
+                    free_move = true;
+                } else {
+                    alive = false;
+                    continue;
+                }
             }
 
             if !free_move {
                 ui.at_turn_start();
+
+                // See if we were killed by a curse
+                if ui.game.state() == GameState::Dead {
+                    continue;
+                }
             } else {
                 free_move = false;
             }
@@ -1714,6 +3168,28 @@ fn main() {
 
                     println!();
 
+                    if let Some("UNDO") = command.get(..4) {
+                        ui.undo(&command[4..]);
+                        free_move = true;
+                        quiet = true;
+                        break;
+                    }
+
+                    if ui.debug && command.get(..5) == Some("DEBUG") {
+                        // Show the room again if something changed, but
+                        // don't spend a turn
+                        if !ui.debug_console() {
+                            quiet = true;
+                        }
+                        free_move = true;
+                        break;
+                    }
+
+                    // Map and help don't change anything worth undoing
+                    if !matches!(command.get(..1), Some("M") | Some("H") | Some("?")) {
+                        ui.game.snapshot();
+                    }
+
                     if let Some("DR") = command.get(..2) {
                         ui.drink();
                         quiet = true;
@@ -1726,10 +3202,26 @@ fn main() {
                             print_stats = false;
                             resolve_room_effects = false;
                         }
-                        Some("N") => ui.move_dir(Direction::North),
-                        Some("S") => ui.move_dir(Direction::South),
-                        Some("W") => ui.move_dir(Direction::West),
-                        Some("E") => ui.move_dir(Direction::East),
+                        Some("N") => {
+                            if !ui.move_dir(Direction::North) {
+                                quiet = true;
+                            }
+                        }
+                        Some("S") => {
+                            if !ui.move_dir(Direction::South) {
+                                quiet = true;
+                            }
+                        }
+                        Some("W") => {
+                            if !ui.move_dir(Direction::West) {
+                                quiet = true;
+                            }
+                        }
+                        Some("E") => {
+                            if !ui.move_dir(Direction::East) {
+                                quiet = true;
+                            }
+                        }
                         Some("U") => {
                             if !ui.move_stairs(Stairs::Up) {
                                 quiet = true;
@@ -1793,7 +3285,6 @@ fn main() {
 
             // See if we were killed by something (exploding chest)
             if ui.game.state() == GameState::Dead {
-                alive = false;
                 continue;
             }
 
@@ -1818,7 +3309,9 @@ fn main() {
 
             print_stats = true;
 
-            if resolve_room_effects {
+            // Keep going until everything in the room has had its turn, or
+            // the player has gone somewhere else
+            while resolve_room_effects && ui.game.state() != GameState::Dead {
                 match ui.game.room_effect() {
                     Event::FoundGold(_) => {
                         println!("\nYOU HAVE {}", ui.game.player_gp());
@@ -1832,8 +3325,8 @@ fn main() {
                     Event::Warp => {
                         automove = true;
                     }
-                    Event::Combat(monster_type) => {
-                        let retreated = ui.combat(monster_type);
+                    Event::Combat(_) => {
+                        let retreated = ui.combat();
 
                         automove = retreated;
                     }
@@ -1842,10 +3335,21 @@ fn main() {
                     }
                     Event::Vendor => {
                         ui.vendor();
+                        resolve_room_effects = false;
                     }
-                    Event::None => (),
+                    event @ (Event::PitTrap(_)
+                    | Event::DartTrap(_)
+                    | Event::AlarmTrap(_)
+                    | Event::SlimeTrap(_)) => {
+                        automove = ui.trap(event);
+                    }
+                    Event::None => resolve_room_effects = false,
+                }
+
+                if automove {
+                    resolve_room_effects = false;
                 }
-            } // if resolve_room_effects
+            } // while resolve_room_effects
 
             resolve_room_effects = true;
 
@@ -1858,6 +3362,8 @@ fn main() {
         } // while alive
 
         ui.game_summary();
+        ui.record_history();
+        ui.record_score();
 
         if !ui.play_again() {
             playing = false;
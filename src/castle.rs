@@ -0,0 +1,463 @@
+use crate::curse::CurseType;
+use crate::difficulty::Difficulty;
+use crate::dungeon::{Dungeon, Edge};
+use crate::error::Error;
+use crate::game::Direction;
+use crate::monster::{Monster, MonsterType};
+use crate::room::{Room, RoomType};
+use crate::treasure::{Treasure, TreasureType};
+
+/// Build a dungeon from the text of a castle file
+///
+/// See the README for the format.
+pub fn parse(text: &str, difficulty: Difficulty) -> Result<Dungeon, Error> {
+    // Line numbers start at 1, and blank lines and comments are skipped
+    let lines: Vec<(usize, String)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim().to_uppercase()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    let end_line = text.lines().count() + 1;
+
+    let mut lines = lines.iter().peekable();
+
+    let next_line =
+        |lines: &mut std::iter::Peekable<_>| lines.next().ok_or(Error::CastleSyntax(end_line));
+
+    // Size
+    let (line_num, line) = next_line(&mut lines)?;
+
+    let (xsize, ysize, zsize) = parse_size(line).ok_or(Error::CastleSyntax(*line_num))?;
+
+    // Levels
+    let mut levels = Vec::new();
+    let mut edges = Vec::new();
+
+    for z in 0..zsize {
+        let (line_num, line) = next_line(&mut lines)?;
+
+        if *line != format!("LEVEL {}", z + 1) {
+            return Err(Error::CastleSyntax(*line_num));
+        }
+
+        let mut level = Vec::new();
+
+        for _ in 0..ysize {
+            let (line_num, line) = next_line(&mut lines)?;
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+
+            if tokens.len() != xsize as usize {
+                return Err(Error::CastleSyntax(*line_num));
+            }
+
+            for t in tokens {
+                level.push(parse_room(t, difficulty).ok_or(Error::CastleSyntax(*line_num))?);
+            }
+        }
+
+        levels.push(level);
+
+        // Any walls and doors come after the rooms
+        while let Some((line_num, line)) = lines.next_if(|(_, l)| !l.starts_with("LEVEL")) {
+            let (x, y, dir, edge) =
+                parse_edge(line, xsize, ysize).ok_or(Error::CastleSyntax(*line_num))?;
+
+            edges.push((x, y, z, dir, edge));
+        }
+    }
+
+    let mut dungeon = Dungeon::from_levels(levels, xsize, ysize, zsize)?;
+
+    for (x, y, z, dir, edge) in edges {
+        dungeon.set_edge(x, y, z, dir, edge);
+    }
+
+    Ok(dungeon)
+}
+
+/// Parse a wall or door line, e.g. "DOOR 3 1 N"
+///
+/// Returns 0-based coordinates.
+fn parse_edge(line: &str, xsize: u32, ysize: u32) -> Option<(u32, u32, Direction, Edge)> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+
+    if parts.len() != 4 {
+        return None;
+    }
+
+    let edge = match parts[0] {
+        "WALL" => Edge::Wall,
+        "DOOR" => Edge::Door,
+        "LOCKED" => Edge::LockedDoor,
+        "SECRET" => Edge::SecretDoor,
+        _ => return None,
+    };
+
+    let x: u32 = parts[1].parse().ok()?;
+    let y: u32 = parts[2].parse().ok()?;
+
+    if x < 1 || y < 1 || x > xsize || y > ysize {
+        return None;
+    }
+
+    let dir = match parts[3] {
+        "N" => Direction::North,
+        "S" => Direction::South,
+        "W" => Direction::West,
+        "E" => Direction::East,
+        _ => return None,
+    };
+
+    Some((x - 1, y - 1, dir, edge))
+}
+
+/// Parse a "SIZE X Y Z" line
+fn parse_size(line: &str) -> Option<(u32, u32, u32)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+
+    if fields.len() != 4 || fields[0] != "SIZE" {
+        return None;
+    }
+
+    let x = fields[1].parse().ok()?;
+    let y = fields[2].parse().ok()?;
+    let z = fields[3].parse().ok()?;
+
+    if x == 0 || y == 0 || z == 0 {
+        return None;
+    }
+
+    Some((x, y, z))
+}
+
+/// Parse a single room
+fn parse_room(token: &str, difficulty: Difficulty) -> Option<Room> {
+    let (token, curse) = match token.split_once('!') {
+        Some((t, c)) => match CurseType::from_name(&c.replace('_', " "))? {
+            CurseType::None => return None,
+            c => (t, c),
+        },
+        None => (token, CurseType::None),
+    };
+
+    let mut room = Room::default();
+    room.set_curse(curse);
+
+    // Empty rooms can't have anything else in them
+    if token == "." {
+        return Some(room);
+    }
+
+    for part in token.split('+') {
+        // Vaults can only hold a treasure or the orb of zot warp
+        let roomtype = match part.strip_prefix("L:") {
+            Some(inside) => match parse_room_type(inside, difficulty)? {
+                t @ (RoomType::Treasure(_) | RoomType::Warp(true)) => RoomType::Vault(Box::new(t)),
+                _ => return None,
+            },
+            None => match parse_room_type(part, difficulty)? {
+                RoomType::Empty => return None,
+                t => t,
+            },
+        };
+
+        room.add(roomtype);
+    }
+
+    room.discovered = room.has(&RoomType::Entrance);
+
+    Some(room)
+}
+
+/// Parse what's in a room, without its curse
+fn parse_room_type(token: &str, difficulty: Difficulty) -> Option<RoomType> {
+    let (glyph, name) = match token.split_once(':') {
+        Some((g, n)) => (g, Some(n.replace('_', " "))),
+        None => (token, None),
+    };
+
+    let roomtype = match (glyph, name) {
+        (".", None) => RoomType::Empty,
+        ("E", None) => RoomType::Entrance,
+        ("D", None) => RoomType::StairsDown,
+        ("U", None) => RoomType::StairsUp,
+        ("G", None) => RoomType::Gold,
+        ("P", None) => RoomType::Pool,
+        ("C", None) => RoomType::Chest,
+        ("F", None) => RoomType::Flares,
+        ("W", None) => RoomType::Warp(false),
+        ("Z", None) => RoomType::Warp(true),
+        ("S", None) => RoomType::Sinkhole,
+        ("O", None) => RoomType::CrystalOrb,
+        ("B", None) => RoomType::Book,
+        ("V", None) => RoomType::Monster(Monster::new(MonsterType::Vendor, false, difficulty)),
+        ("M", Some(name)) => RoomType::Monster(Monster::new(
+            MonsterType::from_name(&name)?,
+            false,
+            difficulty,
+        )),
+        ("R", Some(name)) => match MonsterType::from_name(&name)? {
+            // Vendors never carry the runestaff
+            MonsterType::Vendor => return None,
+            m => RoomType::Monster(Monster::new(m, true, difficulty)),
+        },
+        ("K", Some(name)) => match MonsterType::from_name(&name)? {
+            MonsterType::Vendor => return None,
+            m => {
+                let mut monster = Monster::new(m, false, difficulty);
+                monster.give_key();
+                RoomType::Monster(monster)
+            }
+        },
+        ("X", Some(name)) => match name.as_str() {
+            "PIT" => RoomType::PitTrap,
+            "DART" => RoomType::DartTrap,
+            "ALARM" => RoomType::AlarmTrap,
+            "SLIME" => RoomType::SlimeTrap,
+            _ => return None,
+        },
+        ("T", Some(name)) => RoomType::Treasure(Treasure {
+            treasure_type: TreasureType::from_name(&name)?,
+        }),
+        _ => return None,
+    };
+
+    Some(roomtype)
+}
+
+/// Return the castle file token for a room
+///
+/// The most significant thing in the room comes first.
+pub(crate) fn room_token(room: &Room) -> String {
+    let mut token = match room.contents() {
+        [] => String::from("."),
+        contents => contents
+            .iter()
+            .map(room_type_token)
+            .collect::<Vec<String>>()
+            .join("+"),
+    };
+
+    if room.curse != CurseType::None {
+        token.push('!');
+        token.push_str(room.curse.name());
+    }
+
+    token.replace(' ', "_")
+}
+
+/// Return the castle file lines for the walls and doors on a level
+///
+/// Each edge is only written once, from the room to its south or east.
+pub(crate) fn edge_lines(dungeon: &Dungeon, z: u32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for y in 0..*dungeon.ysize() {
+        for x in 0..*dungeon.xsize() {
+            for (dir, d) in [(Direction::North, "N"), (Direction::West, "W")] {
+                let edge = match dungeon.edge(x, y, z, dir) {
+                    Edge::Open => continue,
+                    Edge::Wall => "WALL",
+                    Edge::Door => "DOOR",
+                    Edge::LockedDoor => "LOCKED",
+                    Edge::SecretDoor => "SECRET",
+                };
+
+                lines.push(format!("{} {} {} {}", edge, x + 1, y + 1, d));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Return the castle file token for what's in a room
+fn room_type_token(roomtype: &RoomType) -> String {
+    match roomtype {
+        RoomType::Empty => String::from("."),
+        RoomType::Entrance => String::from("E"),
+        RoomType::StairsDown => String::from("D"),
+        RoomType::StairsUp => String::from("U"),
+        RoomType::Gold => String::from("G"),
+        RoomType::Pool => String::from("P"),
+        RoomType::Chest => String::from("C"),
+        RoomType::Flares => String::from("F"),
+        RoomType::Warp(false) => String::from("W"),
+        RoomType::Warp(true) => String::from("Z"),
+        RoomType::Sinkhole => String::from("S"),
+        RoomType::CrystalOrb => String::from("O"),
+        RoomType::Book => String::from("B"),
+        RoomType::Monster(ref m) => match m.monster_type() {
+            MonsterType::Vendor => String::from("V"),
+            t => {
+                let glyph = if m.has_runestaff() {
+                    "R"
+                } else if m.has_key() {
+                    "K"
+                } else {
+                    "M"
+                };
+                format!("{}:{}", glyph, t.name())
+            }
+        },
+        RoomType::Treasure(ref t) => format!("T:{}", t.treasure_type.name()),
+        RoomType::Vault(ref inside) => format!("L:{}", room_type_token(inside)),
+        RoomType::PitTrap => String::from("X:PIT"),
+        RoomType::DartTrap => String::from("X:DART"),
+        RoomType::AlarmTrap => String::from("X:ALARM"),
+        RoomType::SlimeTrap => String::from("X:SLIME"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The smallest castle that can be played
+    const TINY: &str = "SIZE 3 1 1\nLEVEL 1\nE Z R:OGRE\n";
+
+    #[test]
+    fn parses_the_tutorial() {
+        let text = include_str!("../castles/tutorial.txt");
+
+        let dungeon = parse(text, Difficulty::Classic).unwrap();
+
+        assert_eq!(
+            (*dungeon.xsize(), *dungeon.ysize(), *dungeon.zsize()),
+            (4, 4, 2)
+        );
+        assert_eq!((dungeon.entrance_x(), dungeon.entrance_y()), (1, 0));
+        assert_eq!(dungeon.orb_of_zot_location(), (3, 0, 1));
+        assert_eq!(dungeon.runestaff_location(), (3, 3, 1));
+
+        let room = dungeon.room_at(2, 1, 1);
+        assert_eq!(*room.curse(), CurseType::Forgetfulness);
+        assert_eq!(room.monster().unwrap().monster_type(), MonsterType::Orc);
+
+        assert!(dungeon.room_at(1, 0, 0).discovered);
+        assert!(!dungeon.room_at(0, 0, 0).discovered);
+    }
+
+    #[test]
+    fn ignores_case_blank_lines_and_comments() {
+        let text = "# comment\n\nsize 3 1 1\n\n  level 1  \ne z r:ogre\n# the end\n";
+
+        assert!(parse(text, Difficulty::Classic).is_ok());
+    }
+
+    #[test]
+    fn reports_the_line_with_a_syntax_error() {
+        let err = |text: &str| parse(text, Difficulty::Classic).err();
+
+        assert_eq!(err(""), Some(Error::CastleSyntax(1)));
+        assert_eq!(err("SIZE 3 1\n"), Some(Error::CastleSyntax(1)));
+        assert_eq!(err("SIZE 3 1 1\nLEVEL 2\n"), Some(Error::CastleSyntax(2)));
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE Z\n"),
+            Some(Error::CastleSyntax(3))
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE Z R:TROLLOP\n"),
+            Some(Error::CastleSyntax(3))
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE Z R:VENDOR\n"),
+            Some(Error::CastleSyntax(3))
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE L:G R:OGRE\n"),
+            Some(Error::CastleSyntax(3))
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE Z R:OGRE\nEXTRA\n"),
+            Some(Error::CastleSyntax(4))
+        );
+
+        // Running out of lines points past the end
+        assert_eq!(err("SIZE 3 1 1\nLEVEL 1\n"), Some(Error::CastleSyntax(3)));
+    }
+
+    #[test]
+    fn checks_the_castle_can_be_won() {
+        let err = |text: &str| parse(text, Difficulty::Classic).err();
+
+        assert_eq!(err(TINY), None);
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\n. Z R:OGRE\n"),
+            Some(Error::CastleEntrance)
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE W R:OGRE\n"),
+            Some(Error::CastleOrbOfZot)
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE Z M:OGRE\n"),
+            Some(Error::CastleRunestaff)
+        );
+        assert_eq!(
+            err("SIZE 3 1 2\nLEVEL 1\nE Z R:OGRE\nLEVEL 2\nU . .\n"),
+            Some(Error::CastleStairs(2))
+        );
+        assert_eq!(
+            err("SIZE 3 1 1\nLEVEL 1\nE L:Z R:OGRE\n"),
+            Some(Error::CastleKeys)
+        );
+        assert_eq!(err("SIZE 3 1 1\nLEVEL 1\nE L:Z R:OGRE+K:ORC\n"), None);
+    }
+
+    #[test]
+    fn scales_monsters_by_difficulty() {
+        let hp = |difficulty| {
+            let dungeon = parse(TINY, difficulty).unwrap();
+            dungeon.room_at(2, 0, 0).monster().unwrap().hp()
+        };
+
+        assert!(hp(Difficulty::Easy) < hp(Difficulty::Classic));
+        assert!(hp(Difficulty::Nightmare) > hp(Difficulty::Classic));
+    }
+
+    #[test]
+    fn reads_walls_and_doors() {
+        let text = format!(
+            "{}WALL 1 1 E\nDOOR 2 1 W\nSECRET 3 1 N\nLOCKED 3 1 S\n",
+            TINY
+        );
+
+        let dungeon = parse(&text, Difficulty::Classic).unwrap();
+
+        // The last line wins when two name the same edge
+        assert_eq!(dungeon.edge(1, 0, 0, Direction::West), Edge::Door);
+        assert_eq!(dungeon.edge(0, 0, 0, Direction::East), Edge::Door);
+        assert_eq!(dungeon.edge(2, 0, 0, Direction::North), Edge::LockedDoor);
+        assert_eq!(dungeon.edge(0, 0, 0, Direction::West), Edge::Open);
+
+        let err = |line: &str| parse(&format!("{}{}\n", TINY, line), Difficulty::Classic).err();
+
+        assert_eq!(err("WALL 4 1 N"), Some(Error::CastleSyntax(4)));
+        assert_eq!(err("WALL 0 1 N"), Some(Error::CastleSyntax(4)));
+        assert_eq!(err("WALL 1 1 UP"), Some(Error::CastleSyntax(4)));
+        assert_eq!(err("FENCE 1 1 N"), Some(Error::CastleSyntax(4)));
+    }
+
+    #[test]
+    fn room_tokens_read_back_the_same() {
+        let text = include_str!("../castles/tutorial.txt");
+        let dungeon = parse(text, Difficulty::Classic).unwrap();
+
+        for z in 0..2 {
+            for y in 0..4 {
+                for x in 0..4 {
+                    let room = dungeon.room_at(x, y, z);
+                    let token = room_token(room);
+                    let again = parse_room(&token, Difficulty::Classic).unwrap();
+
+                    assert_eq!(room.contents(), again.contents(), "{}", token);
+                    assert_eq!(room.curse(), again.curse(), "{}", token);
+                }
+            }
+        }
+    }
+}
@@ -8,6 +8,29 @@ pub enum CurseType {
     Lethargy,
 }
 
+impl CurseType {
+    /// Return the curse's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            CurseType::None => "NONE",
+            CurseType::Forgetfulness => "FORGETFULNESS",
+            CurseType::TheLeech => "THE LEECH",
+            CurseType::Lethargy => "LETHARGY",
+        }
+    }
+
+    /// Return the curse with a name
+    pub fn from_name(name: &str) -> Option<CurseType> {
+        match name {
+            "NONE" => Some(CurseType::None),
+            "FORGETFULNESS" => Some(CurseType::Forgetfulness),
+            "THE LEECH" => Some(CurseType::TheLeech),
+            "LETHARGY" => Some(CurseType::Lethargy),
+            _ => None,
+        }
+    }
+}
+
 pub struct Curse {}
 
 impl Curse {
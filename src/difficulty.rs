@@ -0,0 +1,194 @@
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Difficulty {
+    Easy,
+    Classic,
+    Hard,
+    Nightmare,
+}
+
+impl Difficulty {
+    /// Return the difficulty's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Classic => "CLASSIC",
+            Difficulty::Hard => "HARD",
+            Difficulty::Nightmare => "NIGHTMARE",
+        }
+    }
+
+    /// Return the difficulty with a name
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "EASY" => Some(Difficulty::Easy),
+            "CLASSIC" => Some(Difficulty::Classic),
+            "HARD" => Some(Difficulty::Hard),
+            "NIGHTMARE" => Some(Difficulty::Nightmare),
+            _ => None,
+        }
+    }
+
+    /// Scale a value by a percentage, rounding, but never below 1
+    fn scale(value: u32, percent: u32) -> u32 {
+        std::cmp::max(1, (value * percent + 50) / 100)
+    }
+
+    /// Return the percentage monster stats and vendor prices are scaled by
+    fn percent(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 75,
+            Difficulty::Classic => 100,
+            Difficulty::Hard => 125,
+            Difficulty::Nightmare => 150,
+        }
+    }
+
+    /// Scale monster hitpoints
+    pub fn monster_hp(&self, hp: u32) -> u32 {
+        Difficulty::scale(hp, self.percent())
+    }
+
+    /// Scale monster damage
+    pub fn monster_damage(&self, damage: u32) -> u32 {
+        Difficulty::scale(damage, self.percent())
+    }
+
+    /// Return the number of GP the player starts with
+    pub fn starting_gp(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 100,
+            Difficulty::Classic => 60,
+            Difficulty::Hard => 40,
+            Difficulty::Nightmare => 20,
+        }
+    }
+
+    /// Scale a vendor price
+    pub fn vendor_price(&self, cost: u32) -> u32 {
+        if cost == 0 {
+            return 0;
+        }
+
+        Difficulty::scale(cost, self.percent())
+    }
+
+    /// Return the number of curse rooms in the castle
+    ///
+    /// When this is more than CURSE_COUNT, the curses repeat.
+    pub fn curse_count(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Classic => crate::curse::CURSE_COUNT,
+            Difficulty::Hard => 4,
+            Difficulty::Nightmare => 6,
+        }
+    }
+
+    /// Return the number of trap rooms on each level
+    ///
+    /// The original game didn't have traps.
+    pub fn trap_count(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Classic => 0,
+            Difficulty::Hard => 2,
+            Difficulty::Nightmare => 3,
+        }
+    }
+
+    /// True if monsters have special abilities
+    ///
+    /// The original game's monsters didn't.
+    pub fn monster_abilities(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// True if each vendor has their own opinion of the player
+    ///
+    /// In the original game attacking one vendor made them all angry, bribing
+    /// one calmed them all down, and prices didn't depend on either.
+    pub fn vendor_reputation(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// True if monsters can lose their nerve and flee
+    ///
+    /// The original game's monsters always fought to the death.
+    pub fn monster_morale(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// True if the player chooses what to bribe monsters with
+    ///
+    /// In the original game the monster picked one of the player's treasures,
+    /// and always took it.
+    pub fn choose_bribe(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// True if each monster type carries its own kind of loot
+    ///
+    /// In the original game every monster just had up to 1000 GP.
+    pub fn monster_loot(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// True if weapons wear down as they're used
+    ///
+    /// In the original game only gargoyles and dragons could break them.
+    pub fn weapon_wear(&self) -> bool {
+        *self != Difficulty::Classic
+    }
+
+    /// Return the number of monster packs on each level
+    ///
+    /// The original game only had one monster to a room.
+    pub fn pack_count(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Classic => 0,
+            Difficulty::Hard => 1,
+            Difficulty::Nightmare => 2,
+        }
+    }
+
+    /// Return the chance out of 8 that an orb shows the real Orb of Zot
+    pub fn orb_truth_chance(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Classic => 3,
+            Difficulty::Hard => 2,
+            Difficulty::Nightmare => 1,
+        }
+    }
+
+    /// Return the chance out of 8 that a chest explodes
+    pub fn chest_explode_chance(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Classic => 2,
+            Difficulty::Hard => 3,
+            Difficulty::Nightmare => 3,
+        }
+    }
+
+    /// Return the chance out of 8 that a chest is full of gas
+    pub fn chest_gas_chance(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Classic => 2,
+            Difficulty::Hard => 2,
+            Difficulty::Nightmare => 3,
+        }
+    }
+
+    /// Return the percentage a final score is scaled by
+    pub fn score_percent(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 50,
+            Difficulty::Classic => 100,
+            Difficulty::Hard => 150,
+            Difficulty::Nightmare => 200,
+        }
+    }
+}
@@ -1,328 +1,177 @@
-extern crate rand;
-
-use self::rand::seq::SliceRandom;
-use self::rand::thread_rng;
-use self::rand::Rng;
-
-use crate::curse::Curse;
+use crate::difficulty::Difficulty;
+use crate::error::Error;
+use crate::game::Direction;
+use crate::generator::{ClassicGenerator, DungeonGenerator};
 use crate::monster::{Monster, MonsterType};
+use crate::rng::GameRng;
 use crate::room::{Room, RoomType};
-use crate::treasure::Treasure;
 
-#[derive(Debug)]
+/// What's between two neighboring rooms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edge {
+    Open,
+    Wall,
+    Door,
+    LockedDoor,
+    SecretDoor, // Looks like a wall until it's found
+}
+
+#[derive(Debug, Clone)]
 pub struct Dungeon {
     levels: Vec<Vec<Room>>,
+    edges: Vec<Vec<[Edge; 2]>>, // North and west edges of each room
     xsize: u32,
     ysize: u32,
     zsize: u32,
+    entrance: (u32, u32),
     orb_of_zot: (u32, u32, u32),
     runestaff: (u32, u32, u32),
 }
 
 impl Dungeon {
-    pub fn new(xsize: u32, ysize: u32, zsize: u32) -> Dungeon {
-        let mut levels: Vec<Vec<Room>> = Vec::new();
-
-        let area = xsize * ysize;
-
-        let mut rng = thread_rng();
+    /// Make a new dungeon with the classic generator
+    pub fn new(
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Dungeon {
+        Dungeon::generate(&ClassicGenerator, rng, xsize, ysize, zsize, difficulty)
+    }
 
-        let orb_of_zot_level = rng.gen_range(0..zsize);
-        let runestaff_level = rng.gen_range(0..zsize);
+    /// Return how many rooms apart two places on a level are
+    ///
+    /// Levels wrap around, so this goes whichever way is shorter.
+    pub fn distance(xsize: u32, ysize: u32, a: (u32, u32), b: (u32, u32)) -> u32 {
+        let dx = a.0.abs_diff(b.0);
+        let dy = a.1.abs_diff(b.1);
 
-        // Add all necessary elements to the level
-        for z in 0..zsize {
-            let mut this_level = Vec::new();
+        std::cmp::min(dx, xsize - dx) + std::cmp::min(dy, ysize - dy)
+    }
 
-            Dungeon::place_ent_stairs(&mut this_level, z, zsize, area);
-            Dungeon::place_items(&mut this_level, orb_of_zot_level, z, area);
-            Dungeon::place_monsters_vendors(&mut this_level, z, area, runestaff_level);
+    /// Make a new dungeon with any generator
+    pub fn generate(
+        generator: &dyn DungeonGenerator,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Dungeon {
+        let levels = generator.levels(rng, xsize, ysize, zsize, difficulty);
+
+        let mut dungeon = match Dungeon::from_levels(levels, xsize, ysize, zsize) {
+            Ok(dungeon) => dungeon,
+            Err(err) => panic!("generator made a bad dungeon: {:?}", err),
+        };
+
+        generator.walls(rng, &mut dungeon);
+
+        dungeon
+    }
 
-            levels.push(this_level);
+    /// Make a dungeon from rooms that have already been laid out
+    ///
+    /// Each level is a row-major list of rooms. The dungeon must have exactly
+    /// one entrance (on the first level), one orb of zot warp and one monster
+    /// with the runestaff, and every staircase must have a matching one on the
+    /// next level. Each locked vault needs a monster with a key.
+    ///
+    /// Every room starts out open to all its neighbors.
+    pub fn from_levels(
+        levels: Vec<Vec<Room>>,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+    ) -> Result<Dungeon, Error> {
+        let area = (xsize * ysize) as usize;
+
+        if levels.len() != zsize as usize || levels.iter().any(|l| l.len() != area) {
+            return Err(Error::OutOfBounds);
         }
 
-        // Curses and treasures
-        Dungeon::place_curse_treasure(&mut levels, zsize);
+        let mut entrances = Vec::new();
+        let mut orbs_of_zot = Vec::new();
+        let mut runestaffs = Vec::new();
 
-        // Run through the levels, padding them with empty rooms, shuffling
-        // them, and moving certain rooms to their proper positions.
+        for (z, l) in levels.iter().enumerate() {
+            for (i, room) in l.iter().enumerate() {
+                let loc = (i as u32 % xsize, i as u32 / xsize, z as u32);
 
-        for l in &mut levels {
-            // Fill the rest with empty
-            while l.len() < area as usize {
-                l.push(Room {
-                    roomtype: RoomType::Empty,
-                    ..Default::default()
-                });
-            }
-
-            // Shuffle the level
-            l.shuffle(&mut rng);
-        }
-
-        // Fix up the stairs and entrance
-        Dungeon::place_fixup(&mut levels, xsize, ysize, zsize, area);
-
-        let mut orb_of_zot = (0, 0, 0);
-        let mut runestaff = (0, 0, 0);
-
-        let mut found_orb_of_zot = false;
-        let mut found_runestaff = false;
-
-        // Find the orb of zot
-        //for z in 0..zsize as usize {
-        // Clippy, you crazy for wanting this line instead:
-        for (z, l) in levels.iter().enumerate().take(zsize as usize) {
-            // Find Orb of Zot (for gazing into orbs)
-            if z as u32 == orb_of_zot_level {
-                for y in 0..ysize {
-                    for x in 0..xsize {
-                        let i = (y * xsize + x) as usize;
-
-                        if let RoomType::Warp(oz) = l[i].roomtype {
-                            if oz {
-                                orb_of_zot = (x, y, z as u32);
-                                found_orb_of_zot = true;
-                            }
+                for roomtype in room.contents() {
+                    match roomtype {
+                        RoomType::Entrance => entrances.push(loc),
+                        RoomType::Warp(true) => orbs_of_zot.push(loc),
+                        RoomType::Vault(ref inside) if **inside == RoomType::Warp(true) => {
+                            orbs_of_zot.push(loc)
                         }
+                        RoomType::Monster(ref m) if m.has_runestaff() => runestaffs.push(loc),
+                        _ => (),
                     }
-                }
-            }
 
-            // Find runestaff
-            for y in 0..ysize as usize {
-                for x in 0..xsize as usize {
-                    let i = y * xsize as usize + x;
-
-                    if let RoomType::Monster(ref m) = levels[z][i].roomtype {
-                        if m.has_runestaff() {
-                            runestaff = (x as u32, y as u32, z as u32);
-                            found_runestaff = true;
+                    // Stairs down need stairs up below them, and vice versa
+                    let stairs_ok = match roomtype {
+                        RoomType::StairsDown => {
+                            z + 1 < levels.len() && levels[z + 1][i].has(&RoomType::StairsUp)
                         }
+                        RoomType::StairsUp => z > 0 && levels[z - 1][i].has(&RoomType::StairsDown),
+                        _ => true,
+                    };
+
+                    if !stairs_ok {
+                        return Err(Error::CastleStairs(z as u32 + 1));
                     }
                 }
             }
         }
 
-        if !found_orb_of_zot {
-            panic!("Couldn't find the orb of zot");
-        }
-
-        if !found_runestaff {
-            panic!("Couldn't find the runestaff");
-        }
-
-        Dungeon {
-            levels,
-            xsize,
-            ysize,
-            zsize,
-            orb_of_zot,
-            runestaff,
-        }
-    }
-
-    /// Place the entryway and the stairs
-    fn place_ent_stairs(this_level: &mut Vec<Room>, z: u32, zsize: u32, area: u32) {
-        let stair_count = area / 32; // 2 in 8x8
-
-        // Entrance
-        if z == 0 {
-            this_level.push(Room {
-                roomtype: RoomType::Entrance,
-                discovered: true,
-                ..Default::default()
-            });
-        }
-
-        // Stairs down
-        if z < zsize - 1 {
-            for _ in 0..stair_count {
-                this_level.push(Room {
-                    roomtype: RoomType::StairsDown,
-                    ..Default::default()
-                });
-            }
-        }
-
-        // Stairs up
-        if z > 0 {
-            for _ in 0..stair_count {
-                this_level.push(Room {
-                    roomtype: RoomType::StairsUp,
-                    ..Default::default()
-                });
-            }
+        if entrances.len() != 1 || entrances[0].2 != 0 {
+            return Err(Error::CastleEntrance);
         }
-    }
 
-    /// Place the items in the dungeon
-    fn place_items(this_level: &mut Vec<Room>, orb_of_zot_level: u32, z: u32, area: u32) {
-        let item_count = area / 21; // 3 in 8x8
-
-        // Items
-        for i in 0..item_count {
-            let orb_of_zot_warp = i == 0 && z == orb_of_zot_level;
-
-            this_level.push(Room {
-                roomtype: RoomType::Gold,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Pool,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Chest,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Flares,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Warp(orb_of_zot_warp),
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Sinkhole,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::CrystalOrb,
-                ..Default::default()
-            });
-            this_level.push(Room {
-                roomtype: RoomType::Book,
-                ..Default::default()
-            });
-        }
-    }
-
-    /// Place monsters and vendors in the dungeon
-    fn place_monsters_vendors(this_level: &mut Vec<Room>, z: u32, area: u32, runestaff_level: u32) {
-        let vendor_count = area / 21; // 3 in 8x8
-        let monster_count = area / 5; // 12 in 8x8
-
-        let mut rng = thread_rng();
-
-        // Monsters
-        let monsters_to_place = [
-            MonsterType::Kobold,
-            MonsterType::Orc,
-            MonsterType::Wolf,
-            MonsterType::Goblin,
-            MonsterType::Ogre,
-            MonsterType::Troll,
-            MonsterType::Bear,
-            MonsterType::Minotaur,
-            MonsterType::Gargoyle,
-            MonsterType::Chimera,
-            MonsterType::Balrog,
-            MonsterType::Dragon,
-            // Not counting Vendors
-        ];
-
-        let num_monsters = monsters_to_place.len();
-
-        let monster_with_runestaff = rng.gen_range(0..monster_count) as usize;
-
-        for i in 0..monster_count as usize {
-            let has_runestaff = i == monster_with_runestaff && z == runestaff_level;
-
-            let m_num = i % num_monsters;
-
-            this_level.push(Room {
-                roomtype: RoomType::Monster(Monster::new(monsters_to_place[m_num], has_runestaff)),
-                ..Default::default()
-            });
+        if orbs_of_zot.len() != 1 {
+            return Err(Error::CastleOrbOfZot);
         }
 
-        // Vendors
-        for _ in 0..vendor_count {
-            this_level.push(Room {
-                roomtype: RoomType::Monster(Monster::new(MonsterType::Vendor, false)),
-                ..Default::default()
-            });
+        if runestaffs.len() != 1 {
+            return Err(Error::CastleRunestaff);
         }
-    }
-
-    /// Place curses and treasures
-    fn place_curse_treasure(levels: &mut Vec<Vec<Room>>, zsize: u32) {
-        let mut rng = thread_rng();
-
-        // Add curse rooms
-        for i in 0..crate::curse::CURSE_COUNT {
-            let curse_level = rng.gen_range(0..zsize) as usize;
 
-            let curse = Curse::get_curse_by_id(i);
+        // Every locked vault needs a key somewhere, or it could never be opened
+        let rooms = levels.iter().flatten();
+        let vaults = rooms.clone().filter(|r| r.is_locked()).count();
+        let keys = rooms
+            .flat_map(|r| r.monsters())
+            .filter(|m| m.has_key())
+            .count();
 
-            levels[curse_level].push(Room {
-                curse,
-                ..Default::default()
-            })
+        if keys < vaults {
+            return Err(Error::CastleKeys);
         }
 
-        // Add treasures
-        for i in 0..crate::treasure::TREASURE_COUNT {
-            let treasure_level = rng.gen_range(0..zsize) as usize;
-
-            levels[treasure_level].push(Room {
-                roomtype: RoomType::Treasure(Treasure::new(i)),
-                ..Default::default()
-            })
-        }
-    }
-
-    /// Fix stairs and entrance on levels
-    fn place_fixup(levels: &mut Vec<Vec<Room>>, xsize: u32, ysize: u32, zsize: u32, area: u32) {
-        let entrance_x = (xsize - 1) / 2;
-
-        for z in 0..zsize as usize {
-            // Fix up the entrance
-            for y in 0..ysize as usize {
-                for x in 0..xsize as usize {
-                    let i = y * xsize as usize + x;
+        let edges = vec![vec![[Edge::Open; 2]; area]; zsize as usize];
 
-                    // Swap the entrance
-                    if levels[z][i].roomtype == RoomType::Entrance {
-                        let i2 = (/*0 * xsize + */entrance_x) as usize;
-
-                        levels[z].swap(i, i2);
-                    }
-                }
-            }
-
-            // Fix up the stairs up
-            if z > 0 {
-                let mut downs = Vec::new();
-                let mut ups = Vec::new();
-
-                for i in 0..area as usize {
-                    if levels[z - 1][i].roomtype == RoomType::StairsDown {
-                        downs.push(i);
-                    }
-                }
-
-                for i in 0..area as usize {
-                    if levels[z][i].roomtype == RoomType::StairsUp {
-                        ups.push(i);
-                    }
-                }
-
-                while !ups.is_empty() {
-                    let up_i = ups.pop().unwrap();
-                    let down_i = downs.pop().unwrap();
-
-                    levels[z].swap(up_i, down_i);
-                }
-            }
-        }
+        Ok(Dungeon {
+            levels,
+            edges,
+            xsize,
+            ysize,
+            zsize,
+            entrance: (entrances[0].0, entrances[0].1),
+            orb_of_zot: orbs_of_zot[0],
+            runestaff: runestaffs[0],
+        })
     }
 
     /// Get the entrance x position
     pub fn entrance_x(&self) -> u32 {
-        (self.xsize - 1) / 2
+        self.entrance.0
+    }
+
+    /// Get the entrance y position
+    pub fn entrance_y(&self) -> u32 {
+        self.entrance.1
     }
 
     /// Return a reference to the room at a location
@@ -342,6 +191,46 @@ impl Dungeon {
         &mut self.levels[z as usize][i as usize]
     }
 
+    /// Return where an edge is stored
+    ///
+    /// Rooms only hold their north and west edges. The south and east ones
+    /// belong to the next room over, wrapping around the level.
+    fn edge_index(&self, x: u32, y: u32, dir: Direction) -> (usize, usize) {
+        let (x, y, side) = match dir {
+            Direction::North => (x, y, 0),
+            Direction::West => (x, y, 1),
+            Direction::South => (x, (y + 1) % self.ysize, 0),
+            Direction::East => ((x + 1) % self.xsize, y, 1),
+        };
+
+        ((y * self.xsize + x) as usize, side)
+    }
+
+    /// Return the edge on one side of a room
+    pub fn edge(&self, x: u32, y: u32, z: u32, dir: Direction) -> Edge {
+        let (i, side) = self.edge_index(x, y, dir);
+
+        self.edges[z as usize][i][side]
+    }
+
+    /// Set the edge on one side of a room
+    ///
+    /// This also changes the other side of the edge in the neighboring room.
+    pub fn set_edge(&mut self, x: u32, y: u32, z: u32, dir: Direction, edge: Edge) {
+        let (i, side) = self.edge_index(x, y, dir);
+
+        self.edges[z as usize][i][side] = edge;
+    }
+
+    /// True if there are any edges that aren't open
+    pub fn has_walls(&self) -> bool {
+        self.edges
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|e| *e != Edge::Open)
+    }
+
     /// Discover a room
     pub fn discover(&mut self, x: u32, y: u32, z: u32) {
         let i = y * self.xsize + x;
@@ -349,6 +238,70 @@ impl Dungeon {
         self.levels[z as usize][i as usize].discovered = true;
     }
 
+    /// Move the first `wanted` monster in one room to another
+    ///
+    /// Everything else stays behind. The runestaff is kept track of if the
+    /// monster carrying it moves. Returns the monster's type, or None if there
+    /// wasn't one to move.
+    pub fn move_monster(
+        &mut self,
+        from: (u32, u32, u32),
+        to: (u32, u32, u32),
+        wanted: impl Fn(&Monster) -> bool,
+    ) -> Option<MonsterType> {
+        let monster = self
+            .room_at_mut(from.0, from.1, from.2)
+            .take_monster(wanted)?;
+
+        let monster_type = monster.monster_type();
+
+        self.place_monster(to, monster);
+
+        Some(monster_type)
+    }
+
+    /// Put a monster in a room
+    ///
+    /// The runestaff is kept track of if the monster is carrying it.
+    pub fn place_monster(&mut self, at: (u32, u32, u32), monster: Monster) {
+        if monster.has_runestaff() {
+            self.runestaff = at;
+        }
+
+        self.room_at_mut(at.0, at.1, at.2)
+            .add(RoomType::Monster(monster));
+    }
+
+    /// Return where every treasure lying out in the open is
+    ///
+    /// Treasures locked in vaults don't count.
+    pub fn loose_treasures(&self) -> Vec<(u32, u32, u32)> {
+        let mut locations = Vec::new();
+
+        for (z, l) in self.levels.iter().enumerate() {
+            for (i, room) in l.iter().enumerate() {
+                if room
+                    .contents()
+                    .iter()
+                    .any(|r| matches!(r, RoomType::Treasure(_)))
+                {
+                    locations.push((i as u32 % self.xsize, i as u32 / self.xsize, z as u32));
+                }
+            }
+        }
+
+        locations
+    }
+
+    /// Return how many vaults are still locked
+    pub fn vault_count(&self) -> u32 {
+        self.levels
+            .iter()
+            .flatten()
+            .filter(|r| r.is_locked())
+            .count() as u32
+    }
+
     /// Return x dimension
     pub fn xsize(&self) -> &u32 {
         &self.xsize
@@ -10,4 +10,19 @@ pub enum Error {
     BribeMustProposition, // Need to proposition successfully before calling bribe()
     VendorNoTreasure,     // If the player is trying to get the vendor to offer on no treasure
     VendorMustOfferTreasure, // Need to offer a treasure before calling accept or reject
+    NotPracticing,        // Undo is only allowed in practice mode
+    NothingToUndo,
+    CastleSyntax(usize), // Line number in a castle file that couldn't be read
+    CastleEntrance,      // Castles need exactly one entrance, on the first level
+    CastleOrbOfZot,      // Castles need exactly one orb of zot warp
+    CastleRunestaff,     // Castles need exactly one monster with the runestaff
+    CastleStairs(u32),   // Level (from 1) with stairs that have no match
+    CastleKeys,          // Castles need a key for every locked vault
+    Wall,                // Walls and undiscovered secret doors can't be walked through
+    Locked,              // Locked doors and vaults need a key
+    NothingToRepair,     // The player's gear is already as good as new
+    NothingToCure,       // The player isn't cursed, blind, or stuck to a book
+    OutOfStock,          // The vendor has none left
+    VendorRefuses,       // The vendor thinks too little of the player to sell to them
+    BribeNotOwned,       // The player offered a treasure they don't have
 }
@@ -0,0 +1,409 @@
+use std::fmt::Write;
+
+use crate::castle::{edge_lines, room_token};
+use crate::curse::CurseType;
+use crate::dungeon::{Dungeon, Edge};
+use crate::game::Direction;
+use crate::monster::MonsterType;
+use crate::room::{Room, RoomType};
+
+/// Size of a room in the SVG, in pixels
+const SVG_CELL: u32 = 32;
+
+/// Space around each level in the SVG
+const SVG_GAP: u32 = 32;
+
+/// Most levels side-by-side in the SVG
+const SVG_COLUMNS: u32 = 4;
+
+/// Return all levels as text grids
+///
+/// This is the same format as castle files, so it can be loaded back in with
+/// `castle::parse()`.
+pub fn to_text(dungeon: &Dungeon) -> String {
+    let (xsize, ysize, zsize) = (*dungeon.xsize(), *dungeon.ysize(), *dungeon.zsize());
+
+    let mut s = String::new();
+
+    writeln!(s, "SIZE {} {} {}", xsize, ysize, zsize).unwrap();
+
+    for z in 0..zsize {
+        // Line the columns up
+        let width = (0..ysize)
+            .flat_map(|y| (0..xsize).map(move |x| (x, y)))
+            .map(|(x, y)| room_token(dungeon.room_at(x, y, z)).len())
+            .max()
+            .unwrap_or(1);
+
+        writeln!(s, "\nLEVEL {}", z + 1).unwrap();
+
+        for y in 0..ysize {
+            let row: Vec<String> = (0..xsize)
+                .map(|x| format!("{:w$}", room_token(dungeon.room_at(x, y, z)), w = width))
+                .collect();
+
+            writeln!(s, "{}", row.join(" ").trim_end()).unwrap();
+        }
+
+        for line in edge_lines(dungeon, z) {
+            writeln!(s, "{}", line).unwrap();
+        }
+    }
+
+    s
+}
+
+/// Return the dungeon as JSON
+///
+/// Coordinates are 1-based, the same as the game shows them. Each level is a
+/// list of rows, and each row a list of rooms. Rooms list their north and west
+/// edges when they aren't open; the south and east ones belong to the next
+/// room over.
+pub fn to_json(dungeon: &Dungeon) -> String {
+    let (xsize, ysize, zsize) = (*dungeon.xsize(), *dungeon.ysize(), *dungeon.zsize());
+
+    let loc = |(x, y, z): (u32, u32, u32)| format!("[{}, {}, {}]", x + 1, y + 1, z + 1);
+
+    let mut s = String::new();
+
+    writeln!(s, "{{").unwrap();
+    writeln!(s, "  \"size\": [{}, {}, {}],", xsize, ysize, zsize).unwrap();
+    writeln!(
+        s,
+        "  \"entrance\": {},",
+        loc((dungeon.entrance_x(), dungeon.entrance_y(), 0))
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "  \"orb_of_zot\": {},",
+        loc(dungeon.orb_of_zot_location())
+    )
+    .unwrap();
+    writeln!(s, "  \"runestaff\": {},", loc(dungeon.runestaff_location())).unwrap();
+    writeln!(s, "  \"levels\": [").unwrap();
+
+    for z in 0..zsize {
+        writeln!(s, "    [").unwrap();
+
+        for y in 0..ysize {
+            let row: Vec<String> = (0..xsize).map(|x| room_json(dungeon, x, y, z)).collect();
+
+            let comma = if y + 1 < ysize { "," } else { "" };
+
+            writeln!(s, "      [{}]{}", row.join(", "), comma).unwrap();
+        }
+
+        let comma = if z + 1 < zsize { "," } else { "" };
+
+        writeln!(s, "    ]{}", comma).unwrap();
+    }
+
+    writeln!(s, "  ]").unwrap();
+    writeln!(s, "}}").unwrap();
+
+    s
+}
+
+/// Return the JSON name of a room type
+fn room_type_str(roomtype: &RoomType) -> &'static str {
+    match roomtype {
+        RoomType::Empty => "EMPTY",
+        RoomType::Entrance => "ENTRANCE",
+        RoomType::StairsDown => "STAIRS DOWN",
+        RoomType::StairsUp => "STAIRS UP",
+        RoomType::Gold => "GOLD",
+        RoomType::Pool => "POOL",
+        RoomType::Chest => "CHEST",
+        RoomType::Flares => "FLARES",
+        RoomType::Warp(_) => "WARP",
+        RoomType::Sinkhole => "SINKHOLE",
+        RoomType::CrystalOrb => "CRYSTAL ORB",
+        RoomType::Book => "BOOK",
+        RoomType::Monster(_) => "MONSTER",
+        RoomType::Treasure(_) => "TREASURE",
+        RoomType::Vault(_) => "VAULT",
+        RoomType::PitTrap => "PIT TRAP",
+        RoomType::DartTrap => "DART TRAP",
+        RoomType::AlarmTrap => "ALARM TRAP",
+        RoomType::SlimeTrap => "SLIME TRAP",
+    }
+}
+
+/// Return a room as a JSON object
+///
+/// The fields describe the most significant thing in the room. Rooms with
+/// more than one thing in them list everything in `contents`.
+fn room_json(dungeon: &Dungeon, x: u32, y: u32, z: u32) -> String {
+    let room = dungeon.room_at(x, y, z);
+
+    let mut fields = Vec::new();
+
+    // Vaults are described by what's inside them
+    let roomtype = match room.room_type() {
+        RoomType::Vault(ref inside) => inside,
+        r => r,
+    };
+
+    fields.push(format!("\"type\": \"{}\"", room_type_str(roomtype)));
+
+    if room.is_locked() {
+        fields.push(String::from("\"locked\": true"));
+    }
+
+    match roomtype {
+        RoomType::Warp(true) => fields.push(String::from("\"orb_of_zot\": true")),
+        RoomType::Monster(ref m) => {
+            fields.push(format!("\"monster\": \"{}\"", m.monster_type().name()));
+
+            if m.has_runestaff() {
+                fields.push(String::from("\"runestaff\": true"));
+            }
+
+            if m.has_key() {
+                fields.push(String::from("\"key\": true"));
+            }
+        }
+        RoomType::Treasure(ref t) => {
+            fields.push(format!("\"treasure\": \"{}\"", t.treasure_type.name()));
+        }
+        _ => (),
+    }
+
+    if room.contents().len() > 1 {
+        let contents: Vec<String> = room
+            .contents()
+            .iter()
+            .map(|r| format!("\"{}\"", room_type_str(r)))
+            .collect();
+
+        fields.push(format!("\"contents\": [{}]", contents.join(", ")));
+    }
+
+    if room.curse != CurseType::None {
+        fields.push(format!("\"curse\": \"{}\"", room.curse.name()));
+    }
+
+    for (name, dir) in [("north", Direction::North), ("west", Direction::West)] {
+        let edge = match dungeon.edge(x, y, z, dir) {
+            Edge::Open => continue,
+            Edge::Wall => "WALL",
+            Edge::Door => "DOOR",
+            Edge::LockedDoor => "LOCKED DOOR",
+            Edge::SecretDoor => "SECRET DOOR",
+        };
+
+        fields.push(format!("\"{}\": \"{}\"", name, edge));
+    }
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Return a poster of the dungeon as SVG
+///
+/// Each level gets its own panel, with lines joining the stairs between
+/// levels. Walls are black, doors brown, and locked doors red. Secret doors are
+/// dashed.
+pub fn to_svg(dungeon: &Dungeon) -> String {
+    let (xsize, ysize, zsize) = (*dungeon.xsize(), *dungeon.ysize(), *dungeon.zsize());
+
+    let columns = std::cmp::min(zsize, SVG_COLUMNS);
+    let rows = zsize.div_ceil(columns);
+
+    let panel_w = xsize * SVG_CELL + SVG_GAP;
+    let panel_h = ysize * SVG_CELL + SVG_GAP;
+
+    // Top left corner of a level's panel
+    let origin = |z: u32| {
+        (
+            (z % columns) * panel_w + SVG_GAP,
+            (z / columns) * panel_h + SVG_GAP,
+        )
+    };
+
+    // Center of a room
+    let center = |x: u32, y: u32, z: u32| {
+        let (ox, oy) = origin(z);
+        (
+            ox + x * SVG_CELL + SVG_CELL / 2,
+            oy + y * SVG_CELL + SVG_CELL / 2,
+        )
+    };
+
+    let mut s = String::new();
+
+    writeln!(
+        s,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"14\" text-anchor=\"middle\">",
+        columns * panel_w + SVG_GAP,
+        rows * panel_h + SVG_GAP
+    )
+    .unwrap();
+
+    writeln!(s, "<rect width=\"100%\" height=\"100%\" fill=\"#fffff8\"/>").unwrap();
+
+    for z in 0..zsize {
+        let (ox, oy) = origin(z);
+
+        writeln!(
+            s,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"start\">LEVEL {}</text>",
+            ox,
+            oy - 8,
+            z + 1
+        )
+        .unwrap();
+
+        for y in 0..ysize {
+            for x in 0..xsize {
+                let room = dungeon.room_at(x, y, z);
+
+                let (cx, cy) = center(x, y, z);
+
+                writeln!(
+                    s,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#888\"/>",
+                    cx - SVG_CELL / 2,
+                    cy - SVG_CELL / 2,
+                    SVG_CELL,
+                    SVG_CELL,
+                    svg_color(room)
+                )
+                .unwrap();
+
+                // Just the glyph, without names or curses
+                let token = room_token(room);
+                let glyph = &token[..1];
+
+                writeln!(s, "<text x=\"{}\" y=\"{}\">{}</text>", cx, cy + 5, glyph).unwrap();
+
+                if room.curse != CurseType::None {
+                    writeln!(
+                        s,
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"3\" fill=\"#a0f\"/>",
+                        cx + SVG_CELL / 2 - 6,
+                        cy - SVG_CELL / 2 + 6
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    // Walls and doors along the top and left of each room
+    for z in 0..zsize {
+        for y in 0..ysize {
+            for x in 0..xsize {
+                let (cx, cy) = center(x, y, z);
+                let (left, top) = (cx - SVG_CELL / 2, cy - SVG_CELL / 2);
+
+                let north = (left, top, left + SVG_CELL, top);
+                let west = (left, top, left, top + SVG_CELL);
+
+                for (dir, line) in [(Direction::North, north), (Direction::West, west)] {
+                    let style = match dungeon.edge(x, y, z, dir) {
+                        Edge::Open => continue,
+                        Edge::Wall => "stroke=\"#000\"",
+                        Edge::Door => "stroke=\"#a60\"",
+                        Edge::LockedDoor => "stroke=\"#c00\"",
+                        Edge::SecretDoor => "stroke=\"#000\" stroke-dasharray=\"2 2\"",
+                    };
+
+                    writeln!(
+                        s,
+                        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {} stroke-width=\"3\"/>",
+                        line.0, line.1, line.2, line.3, style
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    // Link stairs down to the stairs up below them
+    for z in 0..zsize.saturating_sub(1) {
+        for y in 0..ysize {
+            for x in 0..xsize {
+                if !dungeon.room_at(x, y, z).has(&RoomType::StairsDown) {
+                    continue;
+                }
+
+                let (x1, y1) = center(x, y, z);
+                let (x2, y2) = center(x, y, z + 1);
+
+                writeln!(
+                    s,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#36c\" \
+                     stroke-width=\"2\" stroke-dasharray=\"4 4\" opacity=\"0.6\"/>",
+                    x1, y1, x2, y2
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    writeln!(s, "</svg>").unwrap();
+
+    s
+}
+
+/// Return the fill color for a room in the SVG
+fn svg_color(room: &Room) -> &'static str {
+    match room.room_type() {
+        RoomType::Empty => "#fff",
+        RoomType::Entrance => "#9d9",
+        RoomType::StairsDown | RoomType::StairsUp => "#9bf",
+        RoomType::Warp(true) => "#fd0",
+        RoomType::Warp(false) | RoomType::Sinkhole => "#ccc",
+        RoomType::Monster(m) => {
+            if m.has_runestaff() {
+                "#f80"
+            } else if m.monster_type() == MonsterType::Vendor {
+                "#dbf"
+            } else {
+                "#f99"
+            }
+        }
+        RoomType::Treasure(_) => "#fe8",
+        RoomType::Vault(_) => "#c93",
+        RoomType::PitTrap | RoomType::DartTrap | RoomType::AlarmTrap | RoomType::SlimeTrap => {
+            "#8c8"
+        }
+        _ => "#eee",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::castle;
+    use crate::difficulty::Difficulty;
+    use crate::generator::{ClassicGenerator, MazeGenerator};
+    use crate::rng::GameRng;
+
+    #[test]
+    fn text_reads_back_the_same_walls_and_all() {
+        let generator = MazeGenerator::new(Box::new(ClassicGenerator));
+        let mut rng = GameRng::new(3);
+
+        let dungeon = Dungeon::generate(&generator, &mut rng, 8, 8, 8, Difficulty::Hard);
+        assert!(dungeon.has_walls());
+
+        let again = castle::parse(&to_text(&dungeon), Difficulty::Hard).unwrap();
+
+        for z in 0..8 {
+            for y in 0..8 {
+                for x in 0..8 {
+                    let (a, b) = (dungeon.room_at(x, y, z), again.room_at(x, y, z));
+
+                    assert_eq!(room_token(a), room_token(b));
+
+                    for dir in [Direction::North, Direction::West] {
+                        assert_eq!(dungeon.edge(x, y, z, dir), again.edge(x, y, z, dir));
+                    }
+                }
+            }
+        }
+    }
+}
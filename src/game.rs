@@ -2,19 +2,28 @@ extern crate rand;
 
 use crate::armor::{Armor, ArmorType};
 use crate::curse::CurseType;
-use crate::dungeon::Dungeon;
+use crate::difficulty::Difficulty;
+use crate::dungeon::{Dungeon, Edge};
 use crate::error::Error;
-use crate::monster::{Monster, MonsterType};
+use crate::generator::{ClassicGenerator, DungeonGenerator};
+use crate::monster::{Ability, BribeTastes, LootTable, Monster, MonsterType};
 use crate::player::{Gender, Player, Race, Stat};
+use crate::reputation::{Reputation, MAX_REPUTATION};
+use crate::rng::{self, GameRng};
 use crate::room::{Room, RoomType};
+use crate::stats::GameStats;
 use crate::treasure::{Treasure, TreasureType};
+use crate::vendor::{Goods, Vendor};
 use crate::weapon::{Weapon, WeaponType};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
 
-use self::rand::thread_rng;
 use self::rand::Rng;
 
+/// How many turns can be undone in practice mode
+pub const UNDO_LIMIT: usize = 100;
+
 #[derive(Debug, Clone)]
 pub enum Event {
     None,
@@ -25,6 +34,10 @@ pub enum Event {
     Treasure(Treasure),
     Combat(MonsterType),
     Vendor,
+    PitTrap(u32),                // Damage taken
+    DartTrap(Option<u32>),       // Damage taken, or None if dodged
+    AlarmTrap(Vec<MonsterType>), // Monsters that came running, if any
+    SlimeTrap(bool),             // True if the armor was destroyed
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,22 +47,54 @@ pub enum CombatEvent {
     Miss,
     Hit(HitResult),
     Died,
-    MonsterWebbed,
-    MonsterMiss,
-    MonsterHit(u32, bool, bool, bool),
+    MonsterWebbed(usize),                     // Which monster being fought
+    MonsterMiss(usize),                       // Which monster being fought
+    MonsterHit(usize, u32, bool, bool, bool), // Which monster, damage, player dead, armor destroyed, web broke
+    MeleeResisted(usize),                     // Which monster shrugged off half the player's blow
+    MonsterRegenerate(usize, u32),            // Which monster, hitpoints healed
+    MonsterAttacksAgain(usize),               // Which monster, before its second attack
+    MonsterBreath(usize, u32, bool),          // Which monster, damage, player dead
+    MonsterPoison(usize, u32),                // Which monster, turns the player is poisoned for
+    MonsterBurnFlares(usize, u32),            // Which monster, flares burned
+    MonsterStealGold(usize, u32),             // Which monster, gold stolen
+    MonsterCharge(usize, u32, bool), // Which monster, damage, player dead, or else knocked into the next room
+    MonsterFled(usize, Direction),   // Which monster, which way it ran
+    WeaponWorn,                      // The player's weapon just got worn, and does less damage
+    WeaponWornOut,                   // The player's weapon fell apart
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct HitResult {
+    pub monster: usize, // Which monster being fought, before any died
+    pub monster_type: MonsterType,
     pub damage: u32,
     pub broke_weapon: bool,
     pub defeated: bool,
-    pub treasure: u32,
-    pub got_runestaff: bool,
+    pub loot: Loot,
     pub killed_vendor: bool,
     pub got_lamp: bool,
 }
 
+/// What the player got off a defeated monster
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Loot {
+    pub gold: u32,
+    pub flares: u32,
+    pub potion: Option<(Stat, u32)>, // Which stat went up, and by how much
+    pub armor_repaired: u32,
+    pub weapon_repaired: u32,
+    pub key: bool,
+    pub treasure: Option<TreasureType>,
+    pub runestaff: bool,
+}
+
+/// What the player offers monsters to leave them alone
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BribeOffer {
+    Treasure(TreasureType),
+    Gold(u32),
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum DrinkEvent {
     Stronger,
@@ -77,6 +122,7 @@ pub enum ChestEvent {
     Explode,
     Gas,
     Treasure(u32),
+    Key,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -144,6 +190,51 @@ pub enum GameState {
     Quit,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeathCause {
+    Monster(MonsterType),
+    ExplodingChest,
+    Fireball,
+    Deathspell,
+    Web,
+    Pool,
+    Orb,
+    PitTrap,
+    DartTrap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Won,
+    Escaped,
+    Died,
+    Quit,
+}
+
+impl Outcome {
+    /// Return the outcome's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Won => "WON",
+            Outcome::Escaped => "ESCAPED",
+            Outcome::Died => "DIED",
+            Outcome::Quit => "QUIT",
+        }
+    }
+
+    /// Return the outcome with a name
+    pub fn from_name(name: &str) -> Option<Outcome> {
+        match name {
+            "WON" => Some(Outcome::Won),
+            "ESCAPED" => Some(Outcome::Escaped),
+            "DIED" => Some(Outcome::Died),
+            "QUIT" => Some(Outcome::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Game {
     dungeon: Dungeon,
     player: Player,
@@ -152,14 +243,17 @@ pub struct Game {
 
     prev_dir: Direction,
 
-    currently_fighting: Option<Monster>,
+    currently_fighting: Vec<Monster>,
+    fighting_from: Vec<Option<usize>>, // Where each one being fought is in the room, while it's there
+    target: usize,                     // Which of the monsters being fought the player goes after
     bribe_possible: bool,
     bribe_treasure: Option<TreasureType>,
     retreating: bool,
 
     spell_possible: bool,
 
-    vendors_angry: bool,
+    reputation: Reputation,
+    vendors_angry: bool, // Only used without vendor reputations
     vendor_treasure_price: Option<HashMap<TreasureType, u32>>,
 
     turn: u32,
@@ -168,36 +262,104 @@ pub struct Game {
     lethargic: bool,
 
     player_moved_since_bribe: bool,
+
+    difficulty: Difficulty,
+
+    stats: GameStats,
+
+    seed: u64,
+    castle_file: Option<String>, // Set if the castle was read from a file, not made from the seed
+    generator: Option<&'static str>, // Set if the castle was made from the seed
+
+    rng: GameRng,
+
+    practice: bool,
+    snapshots: VecDeque<Box<Game>>, // Copies taken before each command, for undo
+    undo_count: u32,
 }
 
 impl Game {
-    pub fn new(xsize: u32, ysize: u32, zsize: u32) -> Game {
-        let dungeon = Dungeon::new(xsize, ysize, zsize);
+    pub fn new(xsize: u32, ysize: u32, zsize: u32, difficulty: Difficulty) -> Game {
+        Game::with_seed(xsize, ysize, zsize, difficulty, rng::random_seed())
+    }
+
+    /// Make a new game, reproducible from the seed
+    pub fn with_seed(
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+        seed: u64,
+    ) -> Game {
+        Game::with_generator(&ClassicGenerator, xsize, ysize, zsize, difficulty, seed)
+    }
+
+    /// Make a new game with a dungeon from any generator
+    pub fn with_generator(
+        generator: &dyn DungeonGenerator,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+        seed: u64,
+    ) -> Game {
+        let mut rng = GameRng::new(seed);
+
+        let dungeon = Dungeon::generate(generator, &mut rng, xsize, ysize, zsize, difficulty);
 
+        let mut game = Game::with_dungeon(dungeon, difficulty, seed, rng);
+        game.generator = Some(generator.name());
+
+        game
+    }
+
+    /// Make a new game in a dungeon that's already been built
+    ///
+    /// The seed is only used for dice rolls.
+    pub fn from_dungeon(dungeon: Dungeon, difficulty: Difficulty, seed: u64) -> Game {
+        Game::with_dungeon(dungeon, difficulty, seed, GameRng::new(seed))
+    }
+
+    /// Set up a game around a dungeon
+    fn with_dungeon(dungeon: Dungeon, difficulty: Difficulty, seed: u64, rng: GameRng) -> Game {
         let mut player = Player::new();
-        player.set_position(dungeon.entrance_x(), 0, 0);
+        player.set_position(dungeon.entrance_x(), dungeon.entrance_y(), 0);
+
+        let reputation = Reputation::new(*dungeon.xsize(), *dungeon.ysize());
 
         Game {
             dungeon,
             player,
             state: GameState::Init,
             prev_dir: Direction::South,
-            currently_fighting: None,
+            currently_fighting: Vec::new(),
+            fighting_from: Vec::new(),
+            target: 0,
             bribe_possible: true,
             bribe_treasure: None,
             retreating: false,
             spell_possible: false,
+            reputation,
             vendors_angry: false,
             vendor_treasure_price: None,
             turn: 0,
             last_recipe_turn: 0,
             lethargic: false,
             player_moved_since_bribe: true,
+            difficulty,
+            stats: GameStats::new(),
+            seed,
+            castle_file: None,
+            generator: None,
+            rng,
+            practice: false,
+            snapshots: VecDeque::new(),
+            undo_count: 0,
         }
     }
 
     /// Get a random monster type
-    fn rand_monster_type() -> MonsterType {
+    fn rand_monster_type(&mut self) -> MonsterType {
         let monster_list = [
             MonsterType::Kobold,
             MonsterType::Orc,
@@ -213,9 +375,7 @@ impl Game {
             MonsterType::Dragon,
         ];
 
-        let mut rng = thread_rng();
-
-        monster_list[rng.gen_range(0..monster_list.len())]
+        monster_list[self.rng.gen_range(0..monster_list.len())]
     }
 
     /// Wrap an x coordinate
@@ -252,8 +412,8 @@ impl Game {
     }
 
     /// Choose a random direction
-    fn rand_direction() -> Direction {
-        match Game::d(1, 4) {
+    fn rand_direction(&mut self) -> Direction {
+        match self.rng.d(1, 4) {
             1 => Direction::North,
             2 => Direction::South,
             3 => Direction::West,
@@ -264,22 +424,20 @@ impl Game {
 
     /// Mark a random room unexplored
     fn rand_mark_unexplored(&mut self) {
-        let mut rng = thread_rng();
+        let x = self.rng.gen_range(0..*self.dungeon.xsize());
+        let y = self.rng.gen_range(0..*self.dungeon.ysize());
+        let z = self.rng.gen_range(0..*self.dungeon.zsize());
 
-        let x = rng.gen_range(0..*self.dungeon.xsize());
-        let y = rng.gen_range(0..*self.dungeon.ysize());
-        let z = rng.gen_range(0..*self.dungeon.zsize());
-
-        self.dungeon.room_at_mut(x, y, z).set_discovered(false);
+        self.dungeon.room_at_mut(x, y, z).forget();
     }
 
-    /// Mark the player's current room as empty
-    fn make_current_room_empty(&mut self) {
+    /// Take one of something out of the player's current room
+    fn remove_from_current_room(&mut self, roomtype: &RoomType) {
         let room = self
             .dungeon
             .room_at_mut(*self.player.x(), *self.player.y(), *self.player.z());
 
-        room.make_empty();
+        room.remove(roomtype);
     }
 
     /// Return the room at the player position
@@ -290,32 +448,134 @@ impl Game {
 
     /// Discover the room at the player position
     pub fn discover_room_at_player(&mut self) {
-        self.dungeon
-            .discover(*self.player.x(), *self.player.y(), *self.player.z())
+        self.discover_room(*self.player.x(), *self.player.y(), *self.player.z())
+    }
+
+    /// Discover a room, counting it if it wasn't already known
+    ///
+    /// The player remembers what's in it as of this turn.
+    fn discover_room(&mut self, x: u32, y: u32, z: u32) {
+        if !self.dungeon.room_at(x, y, z).discovered {
+            self.stats.rooms_discovered += 1;
+        }
+
+        let turn = self.turn;
+
+        self.dungeon.room_at_mut(x, y, z).remember(turn);
     }
 
     /// Handle Gold room effects
     fn room_effect_gold(&mut self) -> Event {
-        let gold_amount = Game::d(1, 10);
+        let gold_amount = self.rng.d(1, 10);
 
         self.player.add_gp(gold_amount as i32);
+        self.stats.gold_found += gold_amount;
 
-        self.make_current_room_empty();
+        self.remove_from_current_room(&RoomType::Gold);
 
         Event::FoundGold(gold_amount)
     }
 
     /// Handle Flare room effects
     fn room_effect_flares(&mut self) -> Event {
-        let flare_amount = Game::d(1, 5);
+        let flare_amount = self.rng.d(1, 5);
 
         self.player.change_flares(flare_amount as i32);
 
-        self.make_current_room_empty();
+        self.remove_from_current_room(&RoomType::Flares);
 
         Event::FoundFlares(flare_amount)
     }
 
+    /// Handle pit trap room effects
+    fn room_effect_pit(&mut self) -> Event {
+        let damage = self.rng.d(1, 4);
+
+        self.stats.damage_taken += damage;
+
+        if self.player.damage_st(damage) {
+            self.die(DeathCause::PitTrap);
+            return Event::PitTrap(damage);
+        }
+
+        let new_z = self.wrap_z(*self.player.z() as i32 + 1);
+
+        self.player.set_z(new_z);
+
+        self.discover_room_at_player();
+
+        Event::PitTrap(damage)
+    }
+
+    /// Handle dart trap room effects
+    fn room_effect_dart(&mut self) -> Event {
+        if self.rng.d(1, 20) <= *self.player.stat(&Stat::Dexterity) {
+            return Event::DartTrap(None);
+        }
+
+        let damage = self.rng.d(1, 4);
+
+        self.stats.damage_taken += damage;
+
+        if self.player.damage_st(damage) {
+            self.die(DeathCause::DartTrap);
+        }
+
+        Event::DartTrap(Some(damage))
+    }
+
+    /// Handle alarm trap room effects
+    ///
+    /// Every monster within two rooms comes running, the closest first.
+    /// Vendors stay where they are.
+    fn room_effect_alarm(&mut self) -> Event {
+        let (px, py, z) = (*self.player.x(), *self.player.y(), *self.player.z());
+
+        let mut nearby = Vec::new();
+
+        for dy in -2..=2i32 {
+            for dx in -2..=2i32 {
+                let distance = dx.abs() + dy.abs();
+
+                if distance == 0 || distance > 2 {
+                    continue;
+                }
+
+                let x = (px as i32 + dx).rem_euclid(*self.dungeon.xsize() as i32) as u32;
+                let y = (py as i32 + dy).rem_euclid(*self.dungeon.ysize() as i32) as u32;
+
+                if (x, y) == (px, py) {
+                    continue;
+                }
+
+                nearby.push((distance, x, y));
+            }
+        }
+
+        nearby.sort_by_key(|&(d, _, _)| d);
+
+        let mut came = Vec::new();
+
+        for (_, x, y) in nearby {
+            while let Some(m) = self.dungeon.move_monster((x, y, z), (px, py, z), |m| {
+                m.monster_type() != MonsterType::Vendor
+            }) {
+                came.push(m);
+            }
+        }
+
+        Event::AlarmTrap(came)
+    }
+
+    /// Handle slime trap room effects
+    fn room_effect_slime(&mut self) -> Event {
+        if self.player.armor().armor_type() == ArmorType::None {
+            return Event::SlimeTrap(false);
+        }
+
+        Event::SlimeTrap(self.player.damage_armor(self.rng.d(2, 4)))
+    }
+
     /// Handle Sinkhole room effects
     fn room_effect_sinkhole(&mut self) -> Event {
         let p_z = *self.player.z() as i32;
@@ -326,51 +586,79 @@ impl Game {
 
         self.discover_room_at_player();
 
+        self.stats.sinkholes += 1;
+
         Event::Sinkhole
     }
 
     /// Handle Warp room effects
     fn room_effect_warp(&mut self, orb_of_zot: bool) -> Event {
         if orb_of_zot {
+            // The warp pushes the player on, walls or no walls
             let prev_dir = self.prev_dir;
-            self.move_dir(prev_dir);
+            self.step(prev_dir);
         } else {
-            let mut rng = thread_rng();
-
-            self.player.set_x(rng.gen_range(0..*self.dungeon.xsize()));
-            self.player.set_y(rng.gen_range(0..*self.dungeon.ysize()));
-            self.player.set_z(rng.gen_range(0..*self.dungeon.zsize()));
+            self.player
+                .set_x(self.rng.gen_range(0..*self.dungeon.xsize()));
+            self.player
+                .set_y(self.rng.gen_range(0..*self.dungeon.ysize()));
+            self.player
+                .set_z(self.rng.gen_range(0..*self.dungeon.zsize()));
         }
 
         self.discover_room_at_player();
 
+        self.stats.warps += 1;
+
         Event::Warp
     }
 
     /// Handle Treasure room effects
     fn room_effect_treasure(&mut self, treasure: Treasure) -> Event {
-        self.make_current_room_empty();
+        self.remove_from_current_room(&RoomType::Treasure(treasure.clone()));
 
         self.player.treasure_add(treasure.treasure_type);
+        self.stats.treasures_collected += 1;
 
         Event::Treasure(treasure)
     }
 
     // Handle Monster room effects
-    fn room_effect_monster(&mut self, monster: &Monster) -> Event {
-        // If the player bribed this monster, don't do combat again until after
-        // the player has moved
+    //
+    // Every monster in the room fights together, except vendors, who only
+    // fight if they've heard bad enough things about the player.
+    fn room_effect_monster(&mut self) -> Event {
+        // If the player bribed these monsters, don't do combat again until
+        // after the player has moved
         if !self.player_moved_since_bribe {
             return Event::None;
         }
 
+        let (fighting_from, pack): (Vec<Option<usize>>, Vec<Monster>) = self
+            .room_at_player()
+            .monsters()
+            .enumerate()
+            .filter(|(_, m)| self.is_hostile(m))
+            .map(|(n, m)| (Some(n), m.clone()))
+            .unzip();
+
         // If Vendors are not angry, head into vendor trade state instead of combat
-        if monster.monster_type() == MonsterType::Vendor && !self.vendors_angry {
+        if pack.is_empty() {
+            let turn = self.turn;
+
+            if let Some(vendor) = self.vendor_mut() {
+                vendor.restock(turn);
+            }
+
             self.state = GameState::Vendor;
             return Event::Vendor;
         }
 
-        self.currently_fighting = Some(monster.clone());
+        let monster_type = pack[0].monster_type();
+
+        self.currently_fighting = pack;
+        self.fighting_from = fighting_from;
+        self.target = 0;
 
         // Monster gets first attack if player blind or lethargic
         if self.lethargic || self.player.is_blind() {
@@ -383,7 +671,13 @@ impl Game {
 
         self.retreating = false;
 
-        Event::Combat(monster.monster_type())
+        Event::Combat(monster_type)
+    }
+
+    /// True if a monster will fight the player on sight
+    fn is_hostile(&self, monster: &Monster) -> bool {
+        monster.monster_type() != MonsterType::Vendor
+            || Reputation::is_hostile(self.vendor_reputation())
     }
 
     /// True if the player can bribe
@@ -396,13 +690,41 @@ impl Game {
         self.spell_possible && *self.player.stat(&Stat::Intelligence) > 14
     }
 
+    /// Get the monsters the player is fighting
+    pub fn fighting(&self) -> &[Monster] {
+        &self.currently_fighting
+    }
+
+    /// Get which of the monsters being fought the player is going after
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Pick which of the monsters being fought to go after
+    ///
+    /// This wasn't in the original game, which only had one monster at a time.
+    pub fn set_target(&mut self, target: usize) -> Result<(), Error> {
+        if target >= self.currently_fighting.len() {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.target = target;
+
+        Ok(())
+    }
+
     /// Handle player attacking monster
-    pub fn attack(&mut self) -> Result<CombatEvent, Error> {
+    ///
+    /// Returns events for anything that happened along with the attack itself.
+    pub fn attack(&mut self) -> Result<Vec<CombatEvent>, Error> {
         self.attack_with(AttackType::Melee)
     }
 
     /// Attack a creature with a given attack type (melee, various magic)
-    fn attack_with(&mut self, attack_type: AttackType) -> Result<CombatEvent, Error> {
+    ///
+    /// Fireballs hit every monster being fought, and everything else hits the
+    /// target. Returns an event for each monster hit.
+    fn attack_with(&mut self, attack_type: AttackType) -> Result<Vec<CombatEvent>, Error> {
         if self.state != GameState::PlayerAttack {
             return Err(Error::WrongState);
         }
@@ -410,20 +732,24 @@ impl Game {
         let hit;
         let mut damage = 0;
 
+        if attack_type != AttackType::Melee {
+            self.stats.spells_cast += 1;
+        }
+
         match attack_type {
             AttackType::Melee => {
                 if self.player.weapon().weapon_type() == WeaponType::None {
                     self.state = GameState::MonsterAttack;
-                    return Ok(CombatEvent::NoWeapon);
+                    return Ok(vec![CombatEvent::NoWeapon]);
                 }
 
                 if *self.player.book_stuck() {
                     self.state = GameState::MonsterAttack;
-                    return Ok(CombatEvent::BookHands);
+                    return Ok(vec![CombatEvent::BookHands]);
                 }
 
                 hit = *self.player.stat(&Stat::Dexterity)
-                    >= (Game::d(1, 20) + (self.player.is_blind() as u32) * 3);
+                    >= (self.rng.d(1, 20) + (self.player.is_blind() as u32) * 3);
 
                 if hit {
                     damage = self.player.weapon().damage();
@@ -434,17 +760,17 @@ impl Game {
                 if self.player.change_stat(Stat::Strength, -1) == 0
                     || self.player.change_stat(Stat::Intelligence, -1) == 0
                 {
-                    self.state = GameState::Dead;
-                    return Ok(CombatEvent::Died);
+                    self.die(DeathCause::Fireball);
+                    return Ok(vec![CombatEvent::Died]);
                 }
 
                 hit = true;
-                damage = Game::d(2, 7);
+                damage = self.rng.d(2, 7);
             }
             AttackType::Deathspell => {
-                if *self.player.stat(&Stat::Intelligence) < 15 + Game::d(1, 4) {
-                    self.state = GameState::Dead;
-                    return Ok(CombatEvent::Died);
+                if *self.player.stat(&Stat::Intelligence) < 15 + self.rng.d(1, 4) {
+                    self.die(DeathCause::Deathspell);
+                    return Ok(vec![CombatEvent::Died]);
                 }
 
                 hit = true;
@@ -452,333 +778,905 @@ impl Game {
             }
         }
 
-        if hit {
+        if !hit {
+            self.state = GameState::MonsterAttack;
+            return Ok(vec![CombatEvent::Miss]);
+        }
+
+        if self.currently_fighting.is_empty() {
+            panic!("not fighting a monster");
+        }
+
+        let targets: Vec<usize> = match attack_type {
+            AttackType::Fireball => (0..self.currently_fighting.len()).collect(),
+            _ => vec![self.target],
+        };
+
+        let mut events = Vec::new();
+
+        for i in targets {
+            let monster = &mut self.currently_fighting[i];
+
             let mut result = HitResult {
+                monster: i,
+                monster_type: monster.monster_type(),
                 damage,
                 broke_weapon: false,
                 defeated: false,
-                treasure: 0,
-                got_runestaff: false,
+                loot: Loot::default(),
                 killed_vendor: false,
                 got_lamp: false,
             };
 
-            let mut next_state = GameState::MonsterAttack;
-
-            if let Some(ref mut monster) = self.currently_fighting {
-                if attack_type == AttackType::Melee
-                    && monster.can_break_weapon()
-                    && Game::d(1, 8) == 1
-                {
-                    result.broke_weapon = true;
-                    self.player.set_weapon(Weapon::new(WeaponType::None));
-                }
+            if attack_type == AttackType::Melee
+                && monster.can_break_weapon()
+                && self.rng.d(1, 8) == 1
+            {
+                result.broke_weapon = true;
+                self.player.set_weapon(Weapon::new(WeaponType::None));
+            }
 
-                result.defeated = monster.take_damage(result.damage);
-
-                if result.defeated {
-                    next_state = GameState::Move;
-
-                    // Take vendor's wares
-                    if monster.monster_type() == MonsterType::Vendor {
-                        result.killed_vendor = true;
-
-                        self.player
-                            .change_stat(Stat::Strength, Game::d(1, 6) as i32);
-                        self.player
-                            .change_stat(Stat::Intelligence, Game::d(1, 6) as i32);
-                        self.player
-                            .change_stat(Stat::Dexterity, Game::d(1, 6) as i32);
-
-                        self.player.set_armor_by_type(ArmorType::Plate);
-                        self.player.set_weapon_by_type(WeaponType::Sword);
-
-                        if !self.player.has_lamp() {
-                            self.player.set_lamp(true);
-                            result.got_lamp = true;
-                        }
-                    } else {
-                        // Non-vendor creature
-                        if monster.has_runestaff() {
-                            self.player.give_runestaff(true);
-                            result.got_runestaff = true;
-                        }
-
-                        result.treasure = Game::d(1, 1000);
-                    }
-                }
-            } else {
-                panic!("not fighting a monster");
+            if attack_type == AttackType::Melee && monster.has_ability(Ability::MeleeResistant) {
+                result.damage = result.damage.div_ceil(2);
+                events.push(CombatEvent::MeleeResisted(i));
             }
 
+            self.stats.damage_dealt += std::cmp::min(result.damage, monster.hp());
+
+            result.defeated = monster.take_damage(result.damage);
+
             if result.defeated {
-                self.make_current_room_empty();
-                self.currently_fighting = None;
+                self.defeat_monster(i, &mut result);
             }
 
-            self.player.add_gp(result.treasure as i32);
+            events.push(CombatEvent::Hit(result));
+        }
+
+        // Every blow wears the weapon down a little
+        if attack_type == AttackType::Melee && self.difficulty.weapon_wear() {
+            let was_worn = self.player.weapon().is_worn();
 
-            self.state = next_state;
-            return Ok(CombatEvent::Hit(result));
+            if self.player.wear_weapon(1) {
+                events.push(CombatEvent::WeaponWornOut);
+            } else if !was_worn && self.player.weapon().is_worn() {
+                events.push(CombatEvent::WeaponWorn);
+            }
         }
 
-        self.state = GameState::MonsterAttack;
-        Ok(CombatEvent::Miss)
-    }
+        // The dead leave the fight
+        let dead: Vec<bool> = self
+            .currently_fighting
+            .iter()
+            .map(|m| m.hp() == 0)
+            .collect();
 
-    /// Helper function to get the next state after a monster attack
-    fn state_after_monster_attack(&mut self) {
-        if self.retreating {
-            self.state = GameState::Retreat;
+        self.leave_fight(&dead);
+
+        if self.currently_fighting.is_empty() {
+            self.state = GameState::Move;
         } else {
-            self.state = GameState::PlayerAttack;
+            self.state = GameState::MonsterAttack;
         }
+
+        Ok(events)
     }
 
-    /// Handle a monster attack
-    pub fn be_attacked(&mut self) -> Result<CombatEvent, Error> {
-        if self.state != GameState::MonsterAttack {
-            return Err(Error::WrongState);
-        }
+    /// Take monsters out of the fight
+    ///
+    /// The player goes after the first monster left if the target is gone.
+    fn leave_fight(&mut self, gone: &[bool]) {
+        let target_gone = gone[self.target];
+        let gone_before_target = gone[..self.target].iter().filter(|g| **g).count();
 
-        self.bribe_possible = false;
-        self.spell_possible = false;
+        let mut i = 0;
 
-        let mut web_broke = false;
+        self.currently_fighting.retain(|_| {
+            i += 1;
+            !gone[i - 1]
+        });
 
-        // Check for web breaking / stuck
-        if let Some(ref mut monster) = self.currently_fighting {
-            if *monster.webbed() > 0 {
-                if monster.weaken_web() {
-                    web_broke = true;
-                } else {
-                    self.state = GameState::PlayerAttack;
-                    return Ok(CombatEvent::MonsterWebbed);
-                }
-            }
+        let mut i = 0;
+
+        self.fighting_from.retain(|_| {
+            i += 1;
+            !gone[i - 1]
+        });
+
+        self.target = if target_gone {
+            0
         } else {
-            panic!("being attacked, but not by any monster");
-        }
+            self.target - gone_before_target
+        };
+    }
 
-        let hit = *self.player.stat(&Stat::Dexterity)
-            < (Game::d(3, 7) + (self.player.is_blind() as u32) * 3);
+    /// Take the `i`th monster being fought out of the player's room
+    ///
+    /// It stays in the fight until `leave_fight()`, but won't be put back in
+    /// the room if the fight ends first.
+    fn take_fought_monster(&mut self, i: usize) -> Option<Monster> {
+        let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
 
-        let mut combat_event = None;
-        let mut defeated = false;
+        let n = self.fighting_from[i].take()?;
 
-        // Handle player hit
-        if hit {
-            if let Some(ref mut monster) = self.currently_fighting {
-                let damage = monster.damage();
-                let armor_value = self.player.armor().armor_value();
-
-                let st_damage = std::cmp::max(damage as isize - armor_value as isize, 0) as u32;
-                defeated = self.player.damage_st(st_damage);
-
-                let armor_damage = std::cmp::min(damage, armor_value);
-                let armor_destroyed = self.player.damage_armor(armor_damage);
-
-                combat_event = Some(CombatEvent::MonsterHit(
-                    st_damage,
-                    defeated,
-                    armor_destroyed,
-                    web_broke,
-                ));
-            } else {
-                panic!("being attacked, but not by any monster");
+        let monster = self.dungeon.room_at_mut(x, y, z).take_nth_monster(n);
+
+        // The room's monsters after it move up one
+        for from in self.fighting_from.iter_mut().flatten() {
+            if *from > n {
+                *from -= 1;
             }
         }
 
-        // Set next state
-        if hit {
-            if defeated {
-                self.state = GameState::Dead;
-            } else {
-                self.state_after_monster_attack();
-            }
+        monster
+    }
+
+    /// Stop fighting, and leave the monsters in the room the fight was in
+    ///
+    /// They keep their wounds and anything they stole for next time.
+    fn end_fight(&mut self, (x, y, z): (u32, u32, u32)) {
+        let fighting = std::mem::take(&mut self.currently_fighting);
+        let fighting_from = std::mem::take(&mut self.fighting_from);
 
-            if let Some(c_event) = combat_event {
-                return Ok(c_event);
+        let room = self.dungeon.room_at_mut(x, y, z);
+
+        // Monsters that were already taken out of the room stay out
+        for (monster, n) in fighting.into_iter().zip(fighting_from) {
+            if let Some(m) = n.and_then(|n| room.monsters_mut().nth(n)) {
+                *m = monster;
             }
         }
+    }
 
-        self.state_after_monster_attack();
+    /// Collect the spoils from the `i`th monster being fought, which was just
+    /// defeated, and take it out of the room
+    fn defeat_monster(&mut self, i: usize, result: &mut HitResult) {
+        let monster = self.currently_fighting[i].clone();
 
-        Ok(CombatEvent::MonsterMiss)
-    }
+        self.stats.add_kill(monster.monster_type());
 
-    /// Handle retreat
-    ///
-    /// This is split out from retreat_dir because the monster gets another
-    /// attack in the middle of it.
-    pub fn retreat(&mut self) -> Result<(), Error> {
-        if self.state != GameState::PlayerAttack {
-            return Err(Error::WrongState);
-        }
+        // Take vendor's wares
+        if monster.monster_type() == MonsterType::Vendor {
+            result.killed_vendor = true;
 
-        self.state = GameState::MonsterAttack;
-        self.retreating = true;
+            self.change_reputation(-20);
 
-        Ok(())
-    }
+            self.player
+                .change_stat(Stat::Strength, self.rng.d(1, 6) as i32);
+            self.player
+                .change_stat(Stat::Intelligence, self.rng.d(1, 6) as i32);
+            self.player
+                .change_stat(Stat::Dexterity, self.rng.d(1, 6) as i32);
 
-    /// Handle bribe
-    pub fn bribe_accept(&mut self) -> Result<(), Error> {
-        if self.state != GameState::PlayerAttack {
-            return Err(Error::WrongState);
-        }
+            self.player.set_armor_by_type(ArmorType::Plate);
+            self.player.set_weapon_by_type(WeaponType::Sword);
 
-        if !self.bribe_possible() {
-            return Err(Error::BribeNotPossible);
+            if !self.player.has_lamp() {
+                self.player.set_lamp(true);
+                result.got_lamp = true;
+            }
+        } else {
+            // Non-vendor creature
+            result.loot = self.roll_loot(&monster);
+            self.take_loot(&mut result.loot);
         }
 
-        if let Some(t_type) = self.bribe_treasure {
-            if self.player.remove_treasure(t_type) {
-                // Player had the treasure
-                self.state = GameState::Move;
-
-                // Check if we're bribing a vendor
-                let roomtype = &self
-                    .dungeon
-                    .room_at(*self.player.x(), *self.player.y(), *self.player.z())
-                    .roomtype;
-
-                if let RoomType::Monster(m) = roomtype {
-                    if m.monster_type() == MonsterType::Vendor {
-                        // If we are, make them unangry
-                        self.vendors_angry = false;
-                    }
-                }
-            } else {
-                panic!("we really thought player had a treasure");
-            }
+        self.take_fought_monster(i);
+    }
 
-            self.bribe_treasure = None;
+    /// Work out what a defeated monster was carrying
+    ///
+    /// That's whatever it was given to guard, anything it stole, and a roll on
+    /// its loot table.
+    fn roll_loot(&mut self, monster: &Monster) -> Loot {
+        let table = if self.difficulty.monster_loot() {
+            Monster::loot_table(monster.monster_type())
         } else {
-            // No current bribeable treasure
-            return Err(Error::BribeMustProposition);
-        }
+            LootTable::classic()
+        };
 
-        self.player_moved_since_bribe = false;
+        let mut loot = Loot {
+            runestaff: monster.has_runestaff(),
+            key: monster.has_key(),
+            ..Loot::default()
+        };
 
-        Ok(())
-    }
+        let (min_gold, max_gold) = table.gold;
 
-    /// Player declines bribe offer
-    pub fn bribe_decline(&mut self) -> Result<(), Error> {
-        if self.state != GameState::PlayerAttack {
-            return Err(Error::WrongState);
-        }
+        loot.gold = min_gold - 1 + self.rng.d(1, max_gold - min_gold + 1) + monster.gold();
 
-        if !self.bribe_possible() {
-            return Err(Error::BribeNotPossible);
+        // The original game only rolled for gold, so don't use up any more dice
+        if !self.difficulty.monster_loot() {
+            return loot;
         }
 
-        self.state = GameState::MonsterAttack;
+        if self.rng.d(1, 20) <= table.flares {
+            loot.flares = self.rng.d(1, 6);
+        }
 
-        Ok(())
-    }
+        if self.rng.d(1, 20) <= table.potion {
+            let stat = match self.rng.d(1, 3) {
+                1 => Stat::Strength,
+                2 => Stat::Intelligence,
+                _ => Stat::Dexterity,
+            };
 
-    /// Get the bribe treasure
-    pub fn bribe_proposition(&mut self) -> Result<Option<TreasureType>, Error> {
-        if self.state != GameState::PlayerAttack {
-            return Err(Error::WrongState);
+            loot.potion = Some((stat, self.rng.d(1, 6)));
         }
 
-        if !self.bribe_possible() {
-            return Err(Error::BribeNotPossible);
+        if self.rng.d(1, 20) <= table.armor_repair {
+            loot.armor_repaired = self.rng.d(1, 7);
         }
 
-        let treasures = self.player.get_treasures();
+        if self.rng.d(1, 20) <= table.whetstone {
+            loot.weapon_repaired = self.rng.d(2, 6);
+        }
 
-        let count = treasures.len();
+        if self.rng.d(1, 20) <= table.key {
+            loot.key = true;
+        }
 
-        if count == 0 {
-            // If you try to bribe with no treasures, the monsters attack
-            self.state = GameState::MonsterAttack;
-            return Ok(None);
+        if self.rng.d(1, 20) <= table.treasure {
+            loot.treasure = self.take_loose_treasure(*self.player.z());
         }
 
-        let mut rng = thread_rng();
+        loot
+    }
 
-        let i = rng.gen_range(0..count);
+    /// Give the player a defeated monster's loot
+    ///
+    /// Armor and weapons can only be repaired as far as new, so the loot is
+    /// updated with how much actually was.
+    fn take_loot(&mut self, loot: &mut Loot) {
+        self.player.add_gp(loot.gold as i32);
+        self.stats.gold_found += loot.gold;
 
-        let t_type = treasures[i];
+        self.player.change_flares(loot.flares as i32);
 
-        self.bribe_treasure = Some(t_type);
+        loot.armor_repaired = self.player.repair_armor(loot.armor_repaired);
+        loot.weapon_repaired = self.player.repair_weapon(loot.weapon_repaired);
 
-        Ok(self.bribe_treasure)
-    }
+        if let Some((stat, amount)) = loot.potion {
+            self.player.change_stat(stat, amount as i32);
+        }
 
-    /// After the monster's final attack
-    pub fn retreat_dir(&mut self, dir: Direction) {
-        self.state = GameState::Move;
+        if loot.key {
+            self.player.change_keys(1);
+        }
 
-        self.move_dir(dir);
+        if let Some(treasure) = loot.treasure {
+            self.player.treasure_add(treasure);
+            self.stats.treasures_collected += 1;
+        }
+
+        if loot.runestaff {
+            self.player.give_runestaff(true);
+        }
+    }
+
+    /// Take a random treasure lying out somewhere on a level
+    ///
+    /// A monster that drops a treasure must have picked it up from somewhere
+    /// it could get to.
+    fn take_loose_treasure(&mut self, z: u32) -> Option<TreasureType> {
+        let locations: Vec<(u32, u32, u32)> = self
+            .dungeon
+            .loose_treasures()
+            .into_iter()
+            .filter(|loc| loc.2 == z)
+            .collect();
+
+        if locations.is_empty() {
+            return None;
+        }
+
+        let (x, y, z) = locations[self.rng.gen_range(0..locations.len())];
+
+        let room = self.dungeon.room_at_mut(x, y, z);
+
+        let treasure = room.contents().iter().find_map(|r| match r {
+            RoomType::Treasure(t) => Some(t.clone()),
+            _ => None,
+        })?;
+
+        room.remove(&RoomType::Treasure(treasure.clone()));
+
+        Some(treasure.treasure_type)
+    }
+
+    /// Helper function to get the next state after a monster attack
+    fn state_after_monster_attack(&mut self) {
+        if self.retreating {
+            self.state = GameState::Retreat;
+        } else {
+            self.state = GameState::PlayerAttack;
+        }
+    }
+
+    /// Handle the monsters attacking
+    ///
+    /// Every monster being fought gets a go, in order, with events for what
+    /// each one does. They stop once the player is dead or knocked out of the
+    /// room. Monsters that lose their nerve run off instead.
+    pub fn be_attacked(&mut self) -> Result<Vec<CombatEvent>, Error> {
+        if self.state != GameState::MonsterAttack {
+            return Err(Error::WrongState);
+        }
+
+        if self.currently_fighting.is_empty() {
+            panic!("being attacked, but not by any monster");
+        }
+
+        self.bribe_possible = false;
+        self.spell_possible = false;
+
+        let mut events = Vec::new();
+        let mut fled = vec![false; self.currently_fighting.len()];
+
+        for (i, monster_fled) in fled.iter_mut().enumerate() {
+            if self.monster_flees(i) {
+                if let Some(dir) = self.escape_dir() {
+                    self.monster_flee(i, dir);
+                    events.push(CombatEvent::MonsterFled(i, dir));
+                    *monster_fled = true;
+                    continue;
+                }
+            }
+
+            self.monster_turn(i, &mut events);
+
+            if self.state != GameState::MonsterAttack {
+                return Ok(events);
+            }
+        }
+
+        self.leave_fight(&fled);
+
+        if self.currently_fighting.is_empty() {
+            self.state = GameState::Move;
+        } else {
+            self.state_after_monster_attack();
+        }
+
+        Ok(events)
+    }
+
+    /// True if a monster loses its nerve this round
+    ///
+    /// Angry vendors would rather run than fight, badly wounded monsters
+    /// might run, and cowards might run from a player with a sword. Webbed
+    /// monsters can't go anywhere. The original game's monsters always fought
+    /// to the death.
+    fn monster_flees(&mut self, i: usize) -> bool {
+        let monster = &self.currently_fighting[i];
+
+        if !self.difficulty.monster_morale() || *monster.webbed() > 0 {
+            return false;
+        }
+
+        if monster.monster_type() == MonsterType::Vendor {
+            return true;
+        }
+
+        if monster.is_wounded() && self.rng.d(1, 3) == 1 {
+            return true;
+        }
+
+        monster.is_cowardly()
+            && self.player.weapon().weapon_type() == WeaponType::Sword
+            && self.rng.d(1, 4) == 1
+    }
+
+    /// Move a fleeing monster into the next room
+    ///
+    /// It takes its wounds and anything it stole with it.
+    fn monster_flee(&mut self, i: usize, dir: Direction) {
+        let monster = self.currently_fighting[i].clone();
+
+        self.take_fought_monster(i);
+
+        let (x, y) = self.next_room(dir);
+        let z = *self.player.z();
+
+        self.dungeon.place_monster((x, y, z), monster);
+    }
+
+    /// Handle one monster's turn
+    fn monster_turn(&mut self, i: usize, events: &mut Vec<CombatEvent>) {
+        let monster = &mut self.currently_fighting[i];
+
+        if monster.has_ability(Ability::Regenerate) {
+            let healed = monster.heal(self.rng.d(1, 3));
+
+            if healed > 0 {
+                events.push(CombatEvent::MonsterRegenerate(i, healed));
+            }
+        }
+
+        let mut web_broke = false;
+
+        // Check for web breaking / stuck
+        if *monster.webbed() > 0 {
+            if monster.weaken_web() {
+                web_broke = true;
+            } else {
+                events.push(CombatEvent::MonsterWebbed(i));
+                return;
+            }
+        }
+
+        let monster = &self.currently_fighting[i];
+
+        // Fire breath can't be dodged, and goes right through armor
+        if monster.has_ability(Ability::Breath) && self.rng.d(1, 4) == 1 {
+            let (damage, defeated, _) = self.hurt_player(i, false);
+            events.push(CombatEvent::MonsterBreath(i, damage, defeated));
+            return;
+        }
+
+        if monster.has_ability(Ability::Charge) && self.rng.d(1, 4) == 1 {
+            if let Some(dir) = self.escape_dir() {
+                let (damage, defeated, _) = self.hurt_player(i, true);
+
+                if !defeated {
+                    // The player lands in the next room, and the fight's over
+                    let at = (*self.player.x(), *self.player.y(), *self.player.z());
+
+                    self.end_fight(at);
+                    self.step(dir);
+                    self.discover_room_at_player();
+                    self.state = GameState::Move;
+                }
+
+                events.push(CombatEvent::MonsterCharge(i, damage, defeated));
+                return;
+            }
+        }
+
+        self.monster_attack(i, web_broke, events);
+
+        let monster = &self.currently_fighting[i];
+
+        if monster.has_ability(Ability::DoubleAttack) && self.state != GameState::Dead {
+            events.push(CombatEvent::MonsterAttacksAgain(i));
+            self.monster_attack(i, false, events);
+        }
+    }
+
+    /// Handle one monster attack, and what its hit does to the player
+    fn monster_attack(&mut self, i: usize, web_broke: bool, events: &mut Vec<CombatEvent>) {
+        let hit = *self.player.stat(&Stat::Dexterity)
+            < (self.rng.d(3, 7) + (self.player.is_blind() as u32) * 3);
+
+        if !hit {
+            events.push(CombatEvent::MonsterMiss(i));
+            return;
+        }
+
+        // Handle player hit
+        let (st_damage, defeated, armor_destroyed) = self.hurt_player(i, true);
+
+        events.push(CombatEvent::MonsterHit(
+            i,
+            st_damage,
+            defeated,
+            armor_destroyed,
+            web_broke,
+        ));
+
+        if defeated {
+            return;
+        }
+
+        let monster = &mut self.currently_fighting[i];
+
+        if monster.has_ability(Ability::Poison) && self.rng.d(1, 2) == 1 {
+            let turns = std::cmp::max(self.player.poisoned(), self.rng.d(1, 4) + 1);
+
+            self.player.set_poisoned(turns, monster.monster_type());
+            events.push(CombatEvent::MonsterPoison(i, turns));
+        }
+
+        if monster.has_ability(Ability::Fire) && self.player.flares() > 0 {
+            let burned = std::cmp::min(self.player.flares(), self.rng.d(1, 4));
+
+            self.player.change_flares(-(burned as i32));
+            events.push(CombatEvent::MonsterBurnFlares(i, burned));
+        }
+
+        if monster.has_ability(Ability::StealGold) && *self.player.gp() > 0 && self.rng.d(1, 2) == 1
+        {
+            let stolen = std::cmp::min(*self.player.gp(), self.rng.d(1, 50));
+
+            self.player.add_gp(-(stolen as i32));
+            monster.add_gold(stolen);
+            events.push(CombatEvent::MonsterStealGold(i, stolen));
+        }
+    }
+
+    /// Hurt the player with a monster's attack
+    ///
+    /// Armor soaks up what it can, and gets damaged doing it, unless
+    /// `armor_helps` is false. Returns the strength lost, whether the player
+    /// died, and whether the armor was destroyed.
+    fn hurt_player(&mut self, i: usize, armor_helps: bool) -> (u32, bool, bool) {
+        let monster = &self.currently_fighting[i];
+
+        let damage = monster.damage();
+        let monster_type = monster.monster_type();
+
+        let armor_value = if armor_helps {
+            self.player.armor().armor_value()
+        } else {
+            0
+        };
+
+        let st_damage = std::cmp::max(damage as isize - armor_value as isize, 0) as u32;
+        let defeated = self.player.damage_st(st_damage);
+        self.stats.damage_taken += st_damage;
+
+        let armor_damage = std::cmp::min(damage, armor_value);
+        let armor_destroyed = armor_helps && self.player.damage_armor(armor_damage);
+
+        if defeated {
+            self.die(DeathCause::Monster(monster_type));
+        }
+
+        (st_damage, defeated, armor_destroyed)
+    }
+
+    /// Pick a random way out of the player's room, if there is one
+    ///
+    /// This is where charging monsters knock the player, and where fleeing
+    /// monsters run.
+    fn escape_dir(&mut self) -> Option<Direction> {
+        let z = *self.player.z();
+
+        let dirs: Vec<Direction> = [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ]
+        .iter()
+        .copied()
+        .filter(|dir| {
+            let (x, y) = self.next_room(*dir);
+
+            matches!(self.player_edge(*dir), Edge::Open | Edge::Door)
+                && !self.dungeon.room_at(x, y, z).is_locked()
+        })
+        .collect();
+
+        if dirs.is_empty() {
+            return None;
+        }
+
+        Some(dirs[self.rng.gen_range(0..dirs.len())])
+    }
+
+    /// Handle retreat
+    ///
+    /// This is split out from retreat_dir because the monster gets another
+    /// attack in the middle of it.
+    pub fn retreat(&mut self) -> Result<(), Error> {
+        if self.state != GameState::PlayerAttack {
+            return Err(Error::WrongState);
+        }
+
+        self.state = GameState::MonsterAttack;
+        self.retreating = true;
+
+        Ok(())
+    }
+
+    /// Handle bribe
+    pub fn bribe_accept(&mut self) -> Result<(), Error> {
+        if self.state != GameState::PlayerAttack {
+            return Err(Error::WrongState);
+        }
+
+        if !self.bribe_possible() {
+            return Err(Error::BribeNotPossible);
+        }
+
+        if let Some(t_type) = self.bribe_treasure {
+            if self.player.remove_treasure(t_type) {
+                // Player had the treasure
+                self.stats.treasures_bribed += 1;
+                self.bribe_taken();
+            } else {
+                panic!("we really thought player had a treasure");
+            }
+
+            self.bribe_treasure = None;
+        } else {
+            // No current bribeable treasure
+            return Err(Error::BribeMustProposition);
+        }
+
+        Ok(())
+    }
+
+    /// Offer the monsters a treasure or gold to leave the player alone
+    ///
+    /// Every monster in the fight has to go for it. Returns true if they took
+    /// it, or false if they turned it down and attack.
+    pub fn bribe_offer(&mut self, offer: BribeOffer) -> Result<bool, Error> {
+        if self.state != GameState::PlayerAttack {
+            return Err(Error::WrongState);
+        }
+
+        if !self.bribe_possible() {
+            return Err(Error::BribeNotPossible);
+        }
+
+        match offer {
+            BribeOffer::Treasure(t_type) => {
+                if !self.player.has_treasure(t_type) {
+                    return Err(Error::BribeNotOwned);
+                }
+            }
+            BribeOffer::Gold(amount) => {
+                if amount > self.player_gp() {
+                    return Err(Error::NotEnoughGP);
+                }
+            }
+        }
+
+        let fighting: Vec<MonsterType> = self
+            .currently_fighting
+            .iter()
+            .map(|m| m.monster_type())
+            .collect();
+
+        let accepted = fighting.into_iter().all(|monster_type| {
+            let tastes = Monster::bribe_tastes(monster_type);
+
+            self.rng.gen_range(0..tastes.price) < Game::bribe_value(tastes, offer)
+        });
+
+        if !accepted {
+            self.state = GameState::MonsterAttack;
+            return Ok(false);
+        }
+
+        match offer {
+            BribeOffer::Treasure(t_type) => {
+                self.player.remove_treasure(t_type);
+                self.stats.treasures_bribed += 1;
+            }
+            BribeOffer::Gold(amount) => self.spend(amount)?,
+        }
+
+        self.bribe_taken();
+
+        Ok(true)
+    }
+
+    /// Return what a bribe is worth to a monster with these tastes
+    fn bribe_value(tastes: BribeTastes, offer: BribeOffer) -> u32 {
+        match offer {
+            BribeOffer::Gold(amount) if tastes.takes_gold => amount,
+            BribeOffer::Gold(_) => 0,
+            BribeOffer::Treasure(t_type) if tastes.favorite == Some(t_type) => {
+                Treasure::treasure_max_value(t_type) * 2
+            }
+            BribeOffer::Treasure(t_type) => Treasure::treasure_max_value(t_type),
+        }
+    }
+
+    /// Let the monsters go once they've been paid off
+    fn bribe_taken(&mut self) {
+        self.state = GameState::Move;
+
+        // Check if we're bribing a vendor
+        let vendor = self
+            .currently_fighting
+            .iter()
+            .any(|m| m.monster_type() == MonsterType::Vendor);
+
+        if vendor {
+            // If we are, word gets around that the player pays up
+            self.change_reputation(30);
+            self.vendors_angry = false;
+        }
+
+        let at = (*self.player.x(), *self.player.y(), *self.player.z());
+        self.end_fight(at);
+
+        self.player_moved_since_bribe = false;
+    }
+
+    /// Player declines bribe offer
+    pub fn bribe_decline(&mut self) -> Result<(), Error> {
+        if self.state != GameState::PlayerAttack {
+            return Err(Error::WrongState);
+        }
+
+        if !self.bribe_possible() {
+            return Err(Error::BribeNotPossible);
+        }
+
+        self.state = GameState::MonsterAttack;
+
+        Ok(())
+    }
+
+    /// Get the bribe treasure
+    pub fn bribe_proposition(&mut self) -> Result<Option<TreasureType>, Error> {
+        if self.state != GameState::PlayerAttack {
+            return Err(Error::WrongState);
+        }
+
+        if !self.bribe_possible() {
+            return Err(Error::BribeNotPossible);
+        }
+
+        let treasures = self.player.get_treasures();
+
+        let count = treasures.len();
+
+        if count == 0 {
+            // If you try to bribe with no treasures, the monsters attack
+            self.state = GameState::MonsterAttack;
+            return Ok(None);
+        }
+
+        let i = self.rng.gen_range(0..count);
+
+        let t_type = treasures[i];
+
+        self.bribe_treasure = Some(t_type);
+
+        Ok(self.bribe_treasure)
+    }
+
+    /// After the monster's final attack
+    pub fn retreat_dir(&mut self, dir: Direction) -> Result<(), Error> {
+        let at = (*self.player.x(), *self.player.y(), *self.player.z());
+
+        self.move_dir(dir)?;
+
+        self.state = GameState::Move;
+        self.end_fight(at);
+
+        Ok(())
     }
 
     /// Fireball spell
-    pub fn spell_fireball(&mut self) -> Result<CombatEvent, Error> {
+    ///
+    /// This hits every monster being fought, with an event for each.
+    pub fn spell_fireball(&mut self) -> Result<Vec<CombatEvent>, Error> {
         self.attack_with(AttackType::Fireball)
     }
 
     /// Deathspell spell
     pub fn spell_deathspell(&mut self) -> Result<CombatEvent, Error> {
-        self.attack_with(AttackType::Deathspell)
+        Ok(self.attack_with(AttackType::Deathspell)?.remove(0))
     }
 
     /// Web spell
+    ///
+    /// Only the target gets stuck.
     pub fn spell_web(&mut self) -> Result<CombatEvent, Error> {
         if self.state != GameState::PlayerAttack {
             return Err(Error::WrongState);
         }
 
+        self.stats.spells_cast += 1;
+
         if self.player.change_stat(Stat::Strength, -1) == 0 {
-            self.state = GameState::Dead;
+            self.die(DeathCause::Web);
             return Ok(CombatEvent::Died);
         }
 
         self.state = GameState::MonsterAttack;
 
+        let monster = match self.currently_fighting.get_mut(self.target) {
+            Some(monster) => monster,
+            None => panic!("not fighting a monster"),
+        };
+
+        monster.set_webbed(self.rng.d(1, 6) + 1);
+
         let result = HitResult {
+            monster: self.target,
+            monster_type: monster.monster_type(),
             damage: 0,
             broke_weapon: false,
             defeated: false,
-            treasure: 0,
-            got_runestaff: false,
+            loot: Loot::default(),
             killed_vendor: false,
             got_lamp: false,
         };
 
-        if let Some(ref mut monster) = self.currently_fighting {
-            monster.set_webbed(Game::d(1, 6) + 1);
-        } else {
-            panic!("not fighting a monster");
-        }
-
         Ok(CombatEvent::Hit(result))
     }
 
     /// Check for a room event
+    ///
+    /// Each call handles the next thing in the room that does something, so
+    /// keep calling until it returns `Event::None` or the player ends up
+    /// somewhere else. Things are handled in this order:
+    ///
+    /// 1. Vaults, which are opened if the player got in by warp or sinkhole
+    /// 2. Traps that haven't gone off yet. The rest of the room still happens
+    ///    on the next call, unless the trap took the player somewhere else.
+    /// 3. Sinkholes and warps
+    /// 4. Monsters, and vendors who won't trade. Nothing else happens until
+    ///    they're gone or bribed.
+    /// 5. Treasures, gold, and flares, which the player picks up one at a time
+    /// 6. Vendors who will trade
     pub fn room_effect(&mut self) -> Event {
-        let roomtype;
+        let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
 
-        {
-            let room = self
-                .dungeon
-                .room_at(*self.player.x(), *self.player.y(), *self.player.z());
-            roomtype = room.roomtype.clone();
+        // Warps and sinkholes get past locks
+        self.unlock_room(x, y, z);
+
+        let room = self.dungeon.room_at(x, y, z);
+
+        if !room.trap_found {
+            if let Some(trap) = room.trap().cloned() {
+                // Traps only go off the first time
+                self.dungeon.room_at_mut(x, y, z).trap_found = true;
+
+                return match trap {
+                    RoomType::PitTrap => self.room_effect_pit(),
+                    RoomType::DartTrap => self.room_effect_dart(),
+                    RoomType::AlarmTrap => self.room_effect_alarm(),
+                    _ => self.room_effect_slime(),
+                };
+            }
         }
 
-        match roomtype {
-            RoomType::Gold => self.room_effect_gold(),
-            RoomType::Flares => self.room_effect_flares(),
-            RoomType::Sinkhole => self.room_effect_sinkhole(),
-            RoomType::Warp(orb_of_zot) => self.room_effect_warp(orb_of_zot),
-            RoomType::Treasure(t) => self.room_effect_treasure(t),
-            RoomType::Monster(m) => self.room_effect_monster(&m),
-            _ => Event::None,
+        let contents = room.contents().to_vec();
+
+        for roomtype in &contents {
+            match roomtype {
+                RoomType::Sinkhole => return self.room_effect_sinkhole(),
+                RoomType::Warp(orb_of_zot) => return self.room_effect_warp(*orb_of_zot),
+                _ => (),
+            }
         }
+
+        // Bribed monsters and calm vendors let the player pick things up
+        let hostile = self.player_moved_since_bribe
+            && self.room_at_player().monsters().any(|m| self.is_hostile(m));
+
+        if hostile {
+            return self.room_effect_monster();
+        }
+
+        for roomtype in contents {
+            match roomtype {
+                RoomType::Treasure(t) => return self.room_effect_treasure(t),
+                RoomType::Gold => return self.room_effect_gold(),
+                RoomType::Flares => return self.room_effect_flares(),
+                _ => (),
+            }
+        }
+
+        if self.room_at_player().monster().is_some() {
+            return self.room_effect_monster();
+        }
+
+        Event::None
+    }
+
+    /// Use up one of the player's keys, if they have any
+    fn use_key(&mut self) -> bool {
+        if self.player.keys() == 0 {
+            return false;
+        }
+
+        self.player.change_keys(-1);
+
+        true
+    }
+
+    /// Open a locked vault, leaving what was inside
+    fn unlock_room(&mut self, x: u32, y: u32, z: u32) {
+        self.dungeon.room_at_mut(x, y, z).unlock();
     }
 
     /// True if the player can teleport
@@ -796,100 +1694,255 @@ impl Game {
             return Err(Error::CantGo);
         }
 
-        if x > 7 || y > 7 || z > 7 {
+        if x >= *self.dungeon.xsize() || y >= *self.dungeon.ysize() || z >= *self.dungeon.zsize() {
             return Err(Error::OutOfBounds);
         }
 
-        {
-            let p = &mut self.player;
-
-            p.set_position(x, y, z);
+        // Locked vaults keep out teleports, too
+        if self.dungeon.room_at(x, y, z).is_locked() {
+            if !self.use_key() {
+                self.discover_room(x, y, z);
+                return Err(Error::Locked);
+            }
+
+            self.unlock_room(x, y, z);
+        }
+
+        {
+            let p = &mut self.player;
+
+            p.set_position(x, y, z);
+
+            let room = self.dungeon.room_at(*p.x(), *p.y(), *p.z());
+
+            if room.has(&RoomType::Warp(true)) {
+                found_orb_of_zot = true;
+                p.give_runestaff(false);
+                p.give_orb_of_zot(true);
+            }
+        }
+
+        if found_orb_of_zot {
+            self.remove_from_current_room(&RoomType::Warp(true));
+        }
+
+        self.player_moved_since_bribe = true;
+
+        self.discover_room_at_player();
+
+        Ok(found_orb_of_zot)
+    }
+
+    /// Handle going up/down stairs
+    pub fn move_stairs(&mut self, dir: Stairs) -> Result<(), Error> {
+        let p = &mut self.player;
+
+        let room = self.dungeon.room_at(*p.x(), *p.y(), *p.z());
+
+        match dir {
+            Stairs::Up => {
+                if !room.has(&RoomType::StairsUp) {
+                    return Err(Error::CantGo);
+                }
+                p.up();
+            }
+            Stairs::Down => {
+                if !room.has(&RoomType::StairsDown) {
+                    return Err(Error::CantGo);
+                }
+                p.down();
+            }
+        }
+
+        self.discover_room_at_player();
+
+        Ok(())
+    }
+
+    /// Handle a move command
+    ///
+    /// Locked doors and vaults are opened if the player has a key. Returns
+    /// true if a key was used.
+    pub fn move_dir(&mut self, dir: Direction) -> Result<bool, Error> {
+        // Handle exit special case
+        if self.room_at_player().has(&RoomType::Entrance) && dir == Direction::North {
+            self.prev_dir = dir;
+            self.state = GameState::Exit;
+            return Ok(false);
+        }
+
+        let mut used_key = false;
+
+        let (x, y) = self.next_room(dir);
+        let z = *self.player.z();
+
+        match self.player_edge(dir) {
+            Edge::Open | Edge::Door => (),
+            Edge::Wall | Edge::SecretDoor => return Err(Error::Wall),
+            Edge::LockedDoor => {
+                if !self.use_key() {
+                    return Err(Error::Locked);
+                }
+
+                let (px, py) = (*self.player.x(), *self.player.y());
+                self.dungeon.set_edge(px, py, z, dir, Edge::Door);
+
+                used_key = true;
+            }
+        }
+
+        if self.dungeon.room_at(x, y, z).is_locked() {
+            if !self.use_key() {
+                // The vault door can be seen from here
+                self.discover_room(x, y, z);
+                return Err(Error::Locked);
+            }
+
+            self.unlock_room(x, y, z);
+
+            used_key = true;
+        }
+
+        self.step(dir);
+
+        Ok(used_key)
+    }
+
+    /// Return the x and y of the next room over from the player
+    fn next_room(&self, dir: Direction) -> (u32, u32) {
+        let (p_x, p_y) = (*self.player.x() as i32, *self.player.y() as i32);
+
+        match dir {
+            Direction::North => (p_x as u32, self.wrap_y(p_y - 1)),
+            Direction::South => (p_x as u32, self.wrap_y(p_y + 1)),
+            Direction::West => (self.wrap_x(p_x - 1), p_y as u32),
+            Direction::East => (self.wrap_x(p_x + 1), p_y as u32),
+        }
+    }
+
+    /// Move the player to the next room over, ignoring walls
+    fn step(&mut self, dir: Direction) {
+        self.prev_dir = dir;
+
+        let (x, y) = self.next_room(dir);
+
+        self.player.set_x(x);
+        self.player.set_y(y);
+
+        self.player_moved_since_bribe = true;
+
+        self.discover_room_at_player();
+    }
+
+    /// Get the vendor in the player's room
+    fn vendor(&self) -> Option<&Vendor> {
+        self.room_at_player().monsters().find_map(|m| m.vendor())
+    }
+
+    /// Get the vendor in the player's room, to change them
+    fn vendor_mut(&mut self) -> Option<&mut Vendor> {
+        let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
+
+        self.dungeon
+            .room_at_mut(x, y, z)
+            .monsters_mut()
+            .find_map(|m| m.vendor_mut())
+    }
+
+    /// Return what the vendor in the player's room thinks of the player
+    fn vendor_reputation(&self) -> i32 {
+        // The original game's vendors were either all angry or all fine
+        if !self.difficulty.vendor_reputation() {
+            return if self.vendors_angry {
+                -MAX_REPUTATION
+            } else {
+                0
+            };
+        }
+
+        let location = (*self.player.x(), *self.player.y(), *self.player.z());
 
-            let room = self.dungeon.room_at(*p.x(), *p.y(), *p.z());
+        let witnessed = match self.vendor() {
+            Some(vendor) => vendor.witnessed(),
+            None => &[],
+        };
 
-            if let RoomType::Warp(true) = room.roomtype {
-                found_orb_of_zot = true;
-                p.give_runestaff(false);
-                p.give_orb_of_zot(true);
-            }
-        }
+        self.reputation.heard_at(location, self.turn, witnessed)
+    }
 
-        if found_orb_of_zot {
-            self.make_current_room_empty();
+    /// Record something the player did for vendors to hear about
+    ///
+    /// Any vendor in the room sees it for themselves.
+    fn change_reputation(&mut self, change: i32) {
+        if !self.difficulty.vendor_reputation() {
+            return;
         }
 
-        self.player_moved_since_bribe = true;
+        let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
 
-        self.discover_room_at_player();
+        let deed = self.reputation.record(self.turn, (x, y, z), change);
 
-        Ok(found_orb_of_zot)
+        for monster in self.dungeon.room_at_mut(x, y, z).monsters_mut() {
+            if let Some(vendor) = monster.vendor_mut() {
+                vendor.witness(deed);
+            }
+        }
     }
 
-    /// Handle going up/down stairs
-    pub fn move_stairs(&mut self, dir: Stairs) -> Result<(), Error> {
-        let p = &mut self.player;
+    /// True if the vendor in the player's room will sell to them
+    ///
+    /// Vendors who don't think much of the player will still buy treasures.
+    pub fn vendor_will_sell(&self) -> bool {
+        Reputation::will_sell(self.vendor_reputation())
+    }
 
-        let room = self.dungeon.room_at(*p.x(), *p.y(), *p.z());
+    /// Return a vendor's price for something
+    ///
+    /// That depends on the difficulty and what they think of the player.
+    fn vendor_price(&self, cost: u32) -> u32 {
+        Reputation::price(self.vendor_reputation(), self.difficulty.vendor_price(cost))
+    }
 
-        match dir {
-            Stairs::Up => {
-                if room.roomtype != RoomType::StairsUp {
-                    return Err(Error::CantGo);
-                }
-                p.up();
-            }
-            Stairs::Down => {
-                if room.roomtype != RoomType::StairsDown {
-                    return Err(Error::CantGo);
-                }
-                p.down();
-            }
+    /// Charge the player for something from a vendor
+    ///
+    /// Every fair trade does the player's reputation a little good.
+    fn vendor_charge(&mut self, price: u32) -> Result<(), Error> {
+        if !self.vendor_will_sell() {
+            return Err(Error::VendorRefuses);
         }
 
-        self.discover_room_at_player();
+        self.spend(price)?;
+        self.change_reputation(2);
 
         Ok(())
     }
 
-    /// Handle a move command
-    pub fn move_dir(&mut self, dir: Direction) {
-        self.prev_dir = dir;
-
-        let roomtype = self.room_at_player().roomtype.clone();
+    /// Return how many of something the vendor in the player's room has
+    pub fn vendor_stock(&self, goods: Goods) -> u32 {
+        self.vendor().map_or(0, |v| v.stock(goods))
+    }
 
-        // Handle exit special case
-        if roomtype == RoomType::Entrance && dir == Direction::North {
-            self.state = GameState::Exit;
-            return;
+    /// Sell the player one of something the vendor has
+    fn vendor_sell(&mut self, goods: Goods, price: u32) -> Result<(), Error> {
+        if self.vendor_stock(goods) == 0 {
+            return Err(Error::OutOfStock);
         }
 
-        let (p_x, p_y) = (*self.player.x() as i32, *self.player.y() as i32);
+        self.vendor_charge(price)?;
 
-        match dir {
-            Direction::North => {
-                let new_y = self.wrap_y(p_y - 1);
-                self.player.set_y(new_y);
-            }
-            Direction::South => {
-                let new_y = self.wrap_y(p_y + 1);
-                self.player.set_y(new_y);
-            }
-            Direction::West => {
-                let new_x = self.wrap_x(p_x - 1);
-                self.player.set_x(new_x);
-            }
-            Direction::East => {
-                let new_x = self.wrap_x(p_x + 1);
-                self.player.set_x(new_x);
-            }
+        if let Some(vendor) = self.vendor_mut() {
+            vendor.sell(goods, price);
         }
 
-        self.player_moved_since_bribe = true;
-
-        self.discover_room_at_player();
+        Ok(())
     }
 
     /// Begin negotiations to sell a treasure to a vendor
+    ///
+    /// The vendor remembers what they thought each treasure was worth, and
+    /// offers less the more treasures they've already bought and more the
+    /// better they think of the player. Treasures they can't pay anything for
+    /// are left out.
     pub fn vendor_treasure_offer(&mut self) -> Result<HashMap<TreasureType, u32>, Error> {
         if self.state != GameState::Vendor {
             return Err(Error::WrongState);
@@ -902,13 +1955,34 @@ impl Game {
         }
 
         let mut hash = HashMap::new();
+        let reputation = self.vendor_reputation();
+
+        for t in treasures {
+            let price = match self.vendor() {
+                Some(vendor) => vendor.treasure_price(t),
+                None => return Err(Error::WrongState),
+            };
+
+            let price = match price {
+                Some(price) => price,
+                None => self.rng.d(1, Treasure::treasure_max_value(t)),
+            };
 
-        for t in self.player_get_treasures() {
-            let max_value = Treasure::treasure_max_value(t);
+            let vendor = match self.vendor_mut() {
+                Some(vendor) => vendor,
+                None => return Err(Error::WrongState),
+            };
+
+            vendor.set_treasure_price(t, price);
 
-            let value = Game::d(1, max_value);
+            let offer = std::cmp::min(
+                Reputation::offer(reputation, vendor.treasure_offer(t)),
+                vendor.cash(),
+            );
 
-            hash.insert(t, value);
+            if offer > 0 {
+                hash.insert(t, offer);
+            }
         }
 
         self.vendor_treasure_price = Some(hash.clone());
@@ -918,7 +1992,7 @@ impl Game {
 
     /// Accept selling a treasure
     pub fn vendor_treasure_accept(&mut self, treasure_type: TreasureType) -> Result<(), Error> {
-        if self.vendor_treasure_price == None {
+        if self.vendor_treasure_price.is_none() {
             return Err(Error::VendorMustOfferTreasure);
         }
 
@@ -928,8 +2002,17 @@ impl Game {
 
         if let Some(ref mut hash) = self.vendor_treasure_price {
             if let Some(value) = hash.get(&treasure_type) {
-                self.player.add_gp(*value as i32);
+                let value = *value;
+
+                self.player.add_gp(value as i32);
+                self.stats.gold_from_vendors += value;
                 hash.insert(treasure_type, 0);
+
+                if let Some(vendor) = self.vendor_mut() {
+                    vendor.buy_treasure(value);
+                }
+
+                self.change_reputation(5);
             } else {
                 return Err(Error::VendorNoTreasure);
             }
@@ -945,7 +2028,7 @@ impl Game {
 
     /// Check if you can afford a specific armor
     pub fn vendor_can_afford_armor_type(&self, armor_type: ArmorType) -> bool {
-        self.player_gp() >= Armor::cost(armor_type, true)
+        self.player_gp() >= self.armor_cost(armor_type, true)
     }
 
     /// Check if you can afford weapons
@@ -955,47 +2038,187 @@ impl Game {
 
     /// Check if you can afford a specific weapon
     pub fn vendor_can_afford_weapon_type(&self, weapon_type: WeaponType) -> bool {
-        self.player_gp() >= Weapon::cost(weapon_type, true)
+        self.player_gp() >= self.weapon_cost(weapon_type, true)
     }
 
     /// Check if you can afford stats
     pub fn vendor_can_afford_stat(&self) -> bool {
-        self.player_gp() >= 1000
+        self.player_gp() >= self.vendor_stat_cost()
     }
 
     /// Check to see if the player can afford anything from the vendor
     pub fn vendor_can_afford_anything(&self) -> bool {
-        // Stats are the cheapest
-        self.vendor_can_afford_stat()
+        // Stats are the cheapest, unless a service is cheaper down here
+        self.vendor_can_afford_stat() || self.player_gp() >= self.vendor_service_cost(250)
     }
 
     /// Buy stats from a vendor
     pub fn vendor_buy_stat(&mut self, stat: Stat) -> Result<u32, Error> {
-        self.player.spend(1000)?;
+        self.vendor_sell(Goods::Potion(stat), self.vendor_stat_cost())?;
 
-        let addition = Game::d(1, 6);
+        let addition = self.rng.d(1, 6);
 
         Ok(self.player.change_stat(stat, addition as i32))
     }
 
     /// True if the player can buy a lamp from a vendor
     pub fn vendor_can_afford_lamp(&self) -> bool {
-        *self.player.gp() >= Game::vendor_lamp_cost()
+        *self.player.gp() >= self.vendor_lamp_cost()
     }
 
     /// Return the cost of a lamp from a vendor
-    pub fn vendor_lamp_cost() -> u32 {
-        1000
+    pub fn vendor_lamp_cost(&self) -> u32 {
+        self.vendor_price(1000)
     }
 
     /// Return the cost of a stat from a vendor
-    pub fn vendor_stat_cost() -> u32 {
-        1000
+    pub fn vendor_stat_cost(&self) -> u32 {
+        self.vendor_price(1000)
+    }
+
+    /// Return the cost of armor, adjusted for difficulty at vendors
+    pub fn armor_cost(&self, armor_type: ArmorType, is_vendor: bool) -> u32 {
+        let cost = Armor::cost(armor_type, is_vendor);
+
+        if is_vendor {
+            self.vendor_price(cost)
+        } else {
+            cost
+        }
+    }
+
+    /// Return the cost of a weapon, adjusted for difficulty at vendors
+    pub fn weapon_cost(&self, weapon_type: WeaponType, is_vendor: bool) -> u32 {
+        let cost = Weapon::cost(weapon_type, is_vendor);
+
+        if is_vendor {
+            self.vendor_price(cost)
+        } else {
+            cost
+        }
+    }
+
+    /// Return the cost of a vendor service
+    ///
+    /// Services cost more the deeper into the castle the player is.
+    fn vendor_service_cost(&self, base: u32) -> u32 {
+        self.vendor_price(base * (*self.player.z() + 1))
+    }
+
+    /// Return the cost of having a weapon repaired by a vendor
+    pub fn vendor_weapon_repair_cost(&self) -> u32 {
+        self.vendor_service_cost(250)
+    }
+
+    /// True if the player can pay a vendor to repair their weapon
+    pub fn vendor_can_afford_weapon_repair(&self) -> bool {
+        *self.player.gp() >= self.vendor_weapon_repair_cost()
+    }
+
+    /// Have a vendor repair the player's weapon as good as new
+    ///
+    /// Returns how much was repaired. This wasn't in the original game.
+    pub fn vendor_repair_weapon(&mut self) -> Result<u32, Error> {
+        if !self.player.weapon().is_worn() {
+            return Err(Error::NothingToRepair);
+        }
+
+        self.vendor_charge(self.vendor_weapon_repair_cost())?;
+
+        Ok(self.player.repair_weapon(u32::MAX))
+    }
+
+    /// Return the cost of having armor repaired by a vendor
+    pub fn vendor_armor_repair_cost(&self) -> u32 {
+        self.vendor_service_cost(250)
+    }
+
+    /// Have a vendor repair the player's armor as good as new
+    ///
+    /// Returns how much was repaired. This wasn't in the original game.
+    pub fn vendor_repair_armor(&mut self) -> Result<u32, Error> {
+        if !self.player.armor().is_damaged() {
+            return Err(Error::NothingToRepair);
+        }
+
+        self.vendor_charge(self.vendor_armor_repair_cost())?;
+
+        Ok(self.player.repair_armor(u32::MAX))
+    }
+
+    /// Have a vendor look the player over for curses
+    ///
+    /// Returns the curse the vendor can lift, if the player has one. This is
+    /// free, but lifting it isn't.
+    pub fn vendor_identify_curse(&self) -> Option<CurseType> {
+        self.player.curses().first().copied()
+    }
+
+    /// Return the cost of having a curse lifted by a vendor
+    pub fn vendor_curse_removal_cost(&self) -> u32 {
+        self.vendor_service_cost(750)
+    }
+
+    /// Have a vendor lift the curse they identified
+    ///
+    /// Returns the curse that was lifted. This wasn't in the original game.
+    pub fn vendor_remove_curse(&mut self) -> Result<CurseType, Error> {
+        let curse = self.vendor_identify_curse().ok_or(Error::NothingToCure)?;
+
+        self.vendor_charge(self.vendor_curse_removal_cost())?;
+
+        self.player.remove_curse(curse);
+
+        if curse == CurseType::Lethargy {
+            self.lethargic = false;
+        }
+
+        Ok(curse)
+    }
+
+    /// Return the cost of having blindness cured by a vendor
+    pub fn vendor_cure_blindness_cost(&self) -> u32 {
+        self.vendor_service_cost(500)
+    }
+
+    /// Have a vendor cure the player's blindness
+    ///
+    /// This wasn't in the original game.
+    pub fn vendor_cure_blindness(&mut self) -> Result<(), Error> {
+        if !self.player.is_blind() {
+            return Err(Error::NothingToCure);
+        }
+
+        self.vendor_charge(self.vendor_cure_blindness_cost())?;
+
+        self.player.set_blind(false);
+
+        Ok(())
+    }
+
+    /// Return the cost of having a book unstuck by a vendor
+    pub fn vendor_unstick_book_cost(&self) -> u32 {
+        self.vendor_service_cost(250)
+    }
+
+    /// Have a vendor get a book off the player's hands
+    ///
+    /// This wasn't in the original game.
+    pub fn vendor_unstick_book(&mut self) -> Result<(), Error> {
+        if !*self.player.book_stuck() {
+            return Err(Error::NothingToCure);
+        }
+
+        self.vendor_charge(self.vendor_unstick_book_cost())?;
+
+        self.player.set_book_stuck(false);
+
+        Ok(())
     }
 
     /// Buy a lamp from a vendor
     pub fn vendor_buy_lamp(&mut self) -> Result<(), Error> {
-        self.player.spend(1000)?;
+        self.vendor_sell(Goods::Lamp, self.vendor_lamp_cost())?;
 
         self.player.set_lamp(true);
 
@@ -1004,6 +2227,7 @@ impl Game {
 
     /// Attack a vendor
     pub fn vendor_attack(&mut self) {
+        self.change_reputation(-30);
         self.vendors_angry = true;
         self.state = GameState::VendorAttack;
     }
@@ -1030,47 +2254,47 @@ impl Game {
 
     /// Drink
     pub fn drink(&mut self) -> Result<DrinkEvent, Error> {
-        let roomtype = self.room_at_player().room_type().clone();
-
-        if roomtype != RoomType::Pool {
+        if !self.room_at_player().has(&RoomType::Pool) {
             return Err(Error::CantGo);
         }
 
-        match Game::d(1, 8) {
+        self.stats.pools_drunk += 1;
+
+        let event = match self.rng.d(1, 8) {
             1 => {
                 self.player
-                    .change_stat(Stat::Strength, Game::d(1, 3) as i32);
+                    .change_stat(Stat::Strength, self.rng.d(1, 3) as i32);
                 Ok(DrinkEvent::Stronger)
             }
             2 => {
                 self.player
-                    .change_stat(Stat::Strength, -(Game::d(1, 3) as i32));
+                    .change_stat(Stat::Strength, -(self.rng.d(1, 3) as i32));
                 Ok(DrinkEvent::Weaker)
             }
             3 => {
                 self.player
-                    .change_stat(Stat::Intelligence, Game::d(1, 3) as i32);
+                    .change_stat(Stat::Intelligence, self.rng.d(1, 3) as i32);
                 Ok(DrinkEvent::Smarter)
             }
             4 => {
                 self.player
-                    .change_stat(Stat::Intelligence, -(Game::d(1, 3) as i32));
+                    .change_stat(Stat::Intelligence, -(self.rng.d(1, 3) as i32));
                 Ok(DrinkEvent::Dumber)
             }
             5 => {
                 self.player
-                    .change_stat(Stat::Dexterity, Game::d(1, 3) as i32);
+                    .change_stat(Stat::Dexterity, self.rng.d(1, 3) as i32);
                 Ok(DrinkEvent::Nimbler)
             }
             6 => {
                 self.player
-                    .change_stat(Stat::Dexterity, -(Game::d(1, 3) as i32));
+                    .change_stat(Stat::Dexterity, -(self.rng.d(1, 3) as i32));
                 Ok(DrinkEvent::Clumsier)
             }
             7 => {
                 let races = [Race::Dwarf, Race::Elf, Race::Hobbit, Race::Human];
 
-                let n = Game::d(1, 3) - 1;
+                let n = self.rng.d(1, 3) - 1;
                 let mut i = 0;
 
                 for _ in 0..n {
@@ -1094,43 +2318,52 @@ impl Game {
                 Ok(DrinkEvent::ChangeGender)
             }
             _ => panic!("should not happen"),
-        }
+        };
+
+        self.check_dead(DeathCause::Pool);
+
+        event
     }
 
     /// Shine the lamp
-    pub fn shine_lamp(&mut self, dir: Direction) -> Result<(u32, u32, u32, RoomType), Error> {
+    ///
+    /// The light gets through open doorways and doors, but not walls or
+    /// locked doors. Secret doors are found, and the light goes through them
+    /// too. The edge the light went through is returned along with the room.
+    ///
+    /// Any trap in the room is found, so it'll show up from now on.
+    pub fn shine_lamp(&mut self, dir: Direction) -> Result<(u32, u32, u32, RoomType, Edge), Error> {
         if !self.player.has_lamp() {
             return Err(Error::CantGo);
         }
 
-        let (x, y);
+        let edge = self.player_edge(dir);
 
-        match dir {
-            Direction::North => {
-                x = *self.player.x();
-                y = self.wrap_y(*self.player.y() as i32 - 1);
-            }
-            Direction::South => {
-                x = *self.player.x();
-                y = self.wrap_y(*self.player.y() as i32 + 1);
-            }
-            Direction::West => {
-                x = self.wrap_x(*self.player.x() as i32 - 1);
-                y = *self.player.y();
-            }
-            Direction::East => {
-                x = self.wrap_x(*self.player.x() as i32 + 1);
-                y = *self.player.y();
+        match edge {
+            Edge::Open | Edge::Door => (),
+            Edge::Wall => return Err(Error::Wall),
+            Edge::LockedDoor => return Err(Error::Locked),
+            Edge::SecretDoor => {
+                let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
+                self.dungeon.set_edge(x, y, z, dir, Edge::Door);
             }
         }
 
+        let (x, y) = self.next_room(dir);
+
         let z = *self.player.z();
 
         let room = self.dungeon.room_at_mut(x, y, z);
 
-        room.set_discovered(true);
+        if room.is_trap() {
+            room.trap_found = true;
+        }
+
+        self.discover_room(x, y, z);
+
+        let room = self.dungeon.room_at(x, y, z);
 
-        Ok((x, y, z, room.room_type().clone()))
+        Ok((x, y, z, room.room_type().clone(), edge))
     }
 
     /// Fire a flare from the player location
@@ -1156,7 +2389,7 @@ impl Game {
             for x in xm1..(xm1 + 3) {
                 let xw = self.wrap_x(x);
 
-                self.dungeon.room_at_mut(xw, yw, z).set_discovered(true);
+                self.discover_room(xw, yw, z);
             }
         }
 
@@ -1165,40 +2398,35 @@ impl Game {
 
     /// Gaze into an Orb
     pub fn gaze(&mut self) -> Result<OrbEvent, Error> {
-        {
-            let room_type = self.room_at_player().room_type();
-
-            if *room_type != RoomType::CrystalOrb {
-                return Err(Error::CantGo);
-            }
+        if !self.room_at_player().has(&RoomType::CrystalOrb) {
+            return Err(Error::CantGo);
         }
 
         if self.player.is_blind() {
             return Err(Error::Blind);
         }
 
-        let mut rng = thread_rng();
-
-        match Game::d(1, 6) {
+        match self.rng.d(1, 6) {
             1 => {
                 self.player
-                    .change_stat(Stat::Strength, -(Game::d(1, 2) as i32));
-                self.make_current_room_empty();
+                    .change_stat(Stat::Strength, -(self.rng.d(1, 2) as i32));
+                self.remove_from_current_room(&RoomType::CrystalOrb);
+                self.check_dead(DeathCause::Orb);
                 Ok(OrbEvent::BloodyHeap)
             }
 
-            2 => Ok(OrbEvent::Polymorph(Game::rand_monster_type())),
+            2 => Ok(OrbEvent::Polymorph(self.rand_monster_type())),
 
-            3 => Ok(OrbEvent::GazeBack(Game::rand_monster_type())),
+            3 => Ok(OrbEvent::GazeBack(self.rand_monster_type())),
 
             4 => {
-                let x = rng.gen_range(0..*self.dungeon.xsize());
-                let y = rng.gen_range(0..*self.dungeon.ysize());
-                let z = rng.gen_range(0..*self.dungeon.zsize());
+                let x = self.rng.gen_range(0..*self.dungeon.xsize());
+                let y = self.rng.gen_range(0..*self.dungeon.ysize());
+                let z = self.rng.gen_range(0..*self.dungeon.zsize());
 
-                let room_type = self.dungeon.room_at(x, y, z).room_type().clone();
+                let room_type = self.dungeon.room_at(x, y, z).seen_type().clone();
 
-                self.dungeon.room_at_mut(x, y, z).set_discovered(true);
+                self.discover_room(x, y, z);
 
                 Ok(OrbEvent::Item(room_type, x, y, z))
             }
@@ -1206,7 +2434,7 @@ impl Game {
             5 => {
                 let (x, y, z);
 
-                if Game::d(1, 8) <= 3 {
+                if self.rng.d(1, 8) <= self.difficulty.orb_truth_chance() {
                     // Actual location
                     let loc = self.dungeon.orb_of_zot_location();
                     x = loc.0;
@@ -1214,9 +2442,9 @@ impl Game {
                     z = loc.2;
                 } else {
                     // Fake location
-                    x = rng.gen_range(0..*self.dungeon.xsize());
-                    y = rng.gen_range(0..*self.dungeon.ysize());
-                    z = rng.gen_range(0..*self.dungeon.zsize());
+                    x = self.rng.gen_range(0..*self.dungeon.xsize());
+                    y = self.rng.gen_range(0..*self.dungeon.ysize());
+                    z = self.rng.gen_range(0..*self.dungeon.zsize());
                 }
 
                 Ok(OrbEvent::OrbOfZot(x, y, z))
@@ -1230,23 +2458,21 @@ impl Game {
 
     /// Open a book
     pub fn open_book(&mut self) -> Result<BookEvent, Error> {
-        {
-            let room_type = self.room_at_player().room_type();
-
-            if *room_type != RoomType::Book {
-                return Err(Error::CantGo);
-            }
+        if !self.room_at_player().has(&RoomType::Book) {
+            return Err(Error::CantGo);
         }
 
-        self.make_current_room_empty();
+        self.stats.books_opened += 1;
+
+        self.remove_from_current_room(&RoomType::Book);
 
-        match Game::d(1, 6) {
+        match self.rng.d(1, 6) {
             1 => {
                 self.player.set_blind(true);
                 Ok(BookEvent::Blind)
             }
             2 => Ok(BookEvent::Poetry),
-            3 => Ok(BookEvent::PlayMonster(Game::rand_monster_type())),
+            3 => Ok(BookEvent::PlayMonster(self.rand_monster_type())),
             4 => {
                 self.player.set_stat(Stat::Dexterity, 18);
                 Ok(BookEvent::Dexterity)
@@ -1265,36 +2491,56 @@ impl Game {
 
     /// Open a chest
     pub fn open_chest(&mut self) -> Result<ChestEvent, Error> {
-        {
-            let room_type = self.room_at_player().room_type();
-
-            if *room_type != RoomType::Chest {
-                return Err(Error::CantGo);
-            }
+        if !self.room_at_player().has(&RoomType::Chest) {
+            return Err(Error::CantGo);
         }
 
+        self.stats.chests_opened += 1;
+
         // In the original game, gas would not destroy the chest.
         // We mod that here to destroy the chest in all cases.
-        self.make_current_room_empty();
+        self.remove_from_current_room(&RoomType::Chest);
 
-        match Game::d(1, 4) {
-            1 => {
-                if self.player.damage_st(Game::d(1, 6)) {
-                    self.state = GameState::Dead;
-                }
-                Ok(ChestEvent::Explode)
-            }
-            2 => {
-                self.add_turn(20);
-                self.move_dir(Game::rand_direction());
-                Ok(ChestEvent::Gas)
+        let explode_chance = self.difficulty.chest_explode_chance();
+        let gas_chance = explode_chance + self.difficulty.chest_gas_chance();
+
+        let roll = self.rng.d(1, 8);
+
+        if roll <= explode_chance {
+            let damage = self.rng.d(1, 6);
+
+            self.stats.damage_taken += damage;
+
+            if self.player.damage_st(damage) {
+                self.die(DeathCause::ExplodingChest);
             }
-            3..=4 => {
-                let gold = Game::d(1, 1000);
-                self.player.add_gp(gold as i32);
-                Ok(ChestEvent::Treasure(gold))
+            Ok(ChestEvent::Explode)
+        } else if roll <= gas_chance {
+            self.add_turn(20);
+            // Staggering into a wall or a lock just leaves the player where
+            // they are
+            let dir = self.rand_direction();
+
+            let (x, y) = self.next_room(dir);
+            let z = *self.player.z();
+
+            let passable = matches!(self.player_edge(dir), Edge::Open | Edge::Door);
+            let vault = self.dungeon.room_at(x, y, z).is_locked();
+
+            if passable && !vault {
+                self.step(dir);
             }
-            _ => panic!("SNR"),
+
+            Ok(ChestEvent::Gas)
+        } else if self.dungeon.vault_count() > self.player.keys() && self.rng.d(1, 3) == 1 {
+            // Chests hold keys while there are vaults the player can't open
+            self.player.change_keys(1);
+            Ok(ChestEvent::Key)
+        } else {
+            let gold = self.rng.d(1, 1000);
+            self.player.add_gp(gold as i32);
+            self.stats.gold_found += gold;
+            Ok(ChestEvent::Treasure(gold))
         }
     }
 
@@ -1342,8 +2588,27 @@ impl Game {
         if self.player.has_curse(CurseType::TheLeech)
             && !self.player.has_treasure(TreasureType::PalePearl)
         {
-            self.player.add_gp(-(Game::d(1, 5) as i32));
+            self.player.add_gp(-(self.rng.d(1, 5) as i32));
+        }
+    }
+
+    /// Handle poison
+    ///
+    /// Returns true if the poison hurt the player this turn.
+    pub fn poison_effects(&mut self) -> bool {
+        let poisoned_by = match self.player.poisoned_by() {
+            Some(monster_type) => monster_type,
+            None => return false,
+        };
+
+        self.player.poison_wear_off();
+        self.stats.damage_taken += 1;
+
+        if self.player.damage_st(1) {
+            self.die(DeathCause::Monster(poisoned_by));
         }
+
+        true
     }
 
     /// Check for catching a curse
@@ -1359,8 +2624,8 @@ impl Game {
     }
 
     /// Choose a random message
-    pub fn rand_message(&self) -> RandomMessage {
-        if Game::d(1, 5) != 1 {
+    pub fn rand_message(&mut self) -> RandomMessage {
+        if self.rng.d(1, 5) != 1 {
             return RandomMessage::None;
         }
 
@@ -1381,9 +2646,7 @@ impl Game {
             msgs.push(RandomMessage::SeeBat);
         }
 
-        let mut rng = thread_rng();
-
-        let i = rng.gen_range(0..msgs.len());
+        let i = self.rng.gen_range(0..msgs.len());
 
         msgs[i]
     }
@@ -1398,22 +2661,112 @@ impl Game {
         }
     }
 
+    /// Kill the player
+    fn die(&mut self, cause: DeathCause) {
+        self.state = GameState::Dead;
+        self.stats.death_cause = Some(cause);
+    }
+
+    /// Kill the player if any stat has hit zero
+    fn check_dead(&mut self, cause: DeathCause) {
+        if self.player.is_dead() {
+            self.die(cause);
+        }
+    }
+
+    /// Return what killed the player, if anything
+    pub fn death_cause(&self) -> Option<DeathCause> {
+        self.stats.death_cause
+    }
+
     /// Quit the game
     pub fn quit(&mut self) {
         self.state = GameState::Quit;
     }
 
-    /// Roll a die (1d6, 2d7, etc.)
-    pub fn d(count: u32, sides: u32) -> u32 {
-        let mut total = 0;
+    /// Spend some of the player's GP
+    fn spend(&mut self, amount: u32) -> Result<(), Error> {
+        self.player.spend(amount)?;
+        self.stats.gold_spent += amount;
+
+        Ok(())
+    }
+
+    /// Turn practice mode on or off
+    ///
+    /// Only practice games can be undone.
+    pub fn set_practice(&mut self, practice: bool) {
+        self.practice = practice;
+
+        if !practice {
+            self.snapshots.clear();
+        }
+    }
+
+    /// True if this is a practice game
+    pub fn practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Return the number of times the player has used undo
+    pub fn undo_count(&self) -> u32 {
+        self.undo_count
+    }
+
+    /// Save the game so this turn can be undone later
+    ///
+    /// Call this before each command. Only one snapshot is kept per turn, and
+    /// nothing is saved outside practice mode.
+    pub fn snapshot(&mut self) {
+        if !self.practice {
+            return;
+        }
+
+        if let Some(s) = self.snapshots.back() {
+            if s.turn == self.turn {
+                return;
+            }
+        }
+
+        // Don't copy the old snapshots into the new one
+        let snapshots = mem::take(&mut self.snapshots);
+        let game = Box::new(self.clone());
+        self.snapshots = snapshots;
 
-        let mut rng = thread_rng();
+        if self.snapshots.len() == UNDO_LIMIT {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(game);
+    }
+
+    /// Roll the game back some number of turns
+    ///
+    /// Returns the number of turns actually undone, which can be fewer than
+    /// asked for if there aren't enough snapshots.
+    pub fn undo(&mut self, turns: u32) -> Result<u32, Error> {
+        if !self.practice {
+            return Err(Error::NotPracticing);
+        }
 
-        for _ in 0..count {
-            total += rng.gen_range(0..sides) + 1;
+        if turns == 0 || self.snapshots.is_empty() {
+            return Err(Error::NothingToUndo);
         }
 
-        total
+        let turns = std::cmp::min(turns as usize, self.snapshots.len());
+
+        let keep = self.snapshots.len() - turns;
+        let snapshot = self.snapshots.split_off(keep).pop_front().unwrap();
+
+        let snapshots = mem::take(&mut self.snapshots);
+        let undo_count = self.undo_count;
+
+        *self = *snapshot;
+
+        self.snapshots = snapshots;
+        self.undo_count = undo_count + 1;
+
+        Ok(turns as u32)
     }
 
     /// Return game state
@@ -1421,6 +2774,56 @@ impl Game {
         self.state
     }
 
+    /// Return the statistics for this game
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    /// Return how the game ended, or None if it's still going
+    pub fn outcome(&self) -> Option<Outcome> {
+        match self.state {
+            GameState::Exit => {
+                if self.player.has_orb_of_zot() {
+                    Some(Outcome::Won)
+                } else {
+                    Some(Outcome::Escaped)
+                }
+            }
+            GameState::Dead => Some(Outcome::Died),
+            GameState::Quit => Some(Outcome::Quit),
+            _ => None,
+        }
+    }
+
+    /// Compute the player's score
+    ///
+    /// Winning is worth 5000. If the player got out of the castle, each
+    /// treasure they carried is worth a tenth of its maximum value, as is
+    /// their gold. One point is lost per turn, and the total is scaled by the
+    /// difficulty.
+    pub fn score(&self) -> u32 {
+        let mut score = 0;
+
+        let outcome = self.outcome();
+
+        if outcome == Some(Outcome::Won) {
+            score += 5000;
+        }
+
+        // Loot only counts if the player made it out with it
+        if outcome == Some(Outcome::Won) || outcome == Some(Outcome::Escaped) {
+            for t in self.player.get_treasures() {
+                score += Treasure::treasure_max_value(t) / 10;
+            }
+
+            score += self.player_gp() / 10;
+        }
+
+        score = score.saturating_sub(self.turn);
+
+        score * self.difficulty.score_percent() / 100
+    }
+
     /// Accessors for player position
     pub fn player_x(&self) -> u32 {
         *self.player.x()
@@ -1473,9 +2876,24 @@ impl Game {
         self.player.armor().armor_type()
     }
 
-    /// Accessor for player weapon type
-    pub fn player_weapon_type(&self) -> WeaponType {
-        self.player.weapon().weapon_type()
+    /// Accessor for player weapon type
+    pub fn player_weapon_type(&self) -> WeaponType {
+        self.player.weapon().weapon_type()
+    }
+
+    /// True if the player's armor has taken any damage
+    pub fn player_armor_damaged(&self) -> bool {
+        self.player.armor().is_damaged()
+    }
+
+    /// True if a book is stuck to the player's hands
+    pub fn player_book_stuck(&self) -> bool {
+        *self.player.book_stuck()
+    }
+
+    /// True if the player's weapon is worn and doing less damage
+    pub fn player_weapon_worn(&self) -> bool {
+        self.player.weapon().is_worn()
     }
 
     /// Accessor for player lamp
@@ -1483,6 +2901,11 @@ impl Game {
         self.player.has_lamp()
     }
 
+    /// Return the number of keys the player has
+    pub fn player_keys(&self) -> u32 {
+        self.player.keys()
+    }
+
     /// Accessor for player flares
     pub fn player_flares(&self) -> u32 {
         self.player.flares()
@@ -1490,7 +2913,7 @@ impl Game {
 
     /// Init the player
     pub fn player_init(&mut self, race: Race) {
-        self.player.init(race);
+        self.player.init(race, self.difficulty.starting_gp());
     }
 
     /// Set player's gender
@@ -1505,12 +2928,34 @@ impl Game {
 
     /// Give the player some armor
     pub fn player_purchase_armor(&mut self, a: ArmorType, is_vendor: bool) -> Result<(), Error> {
-        self.player.purchase_armor(a, is_vendor)
+        let cost = self.armor_cost(a, is_vendor);
+
+        if is_vendor {
+            self.vendor_sell(Goods::Armor(a), cost)?;
+            self.player.set_armor_by_type(a);
+            return Ok(());
+        }
+
+        self.player.purchase_armor(a, cost)?;
+        self.stats.gold_spent += cost;
+
+        Ok(())
     }
 
     /// Give the player a weapon
     pub fn player_purchase_weapon(&mut self, w: WeaponType, is_vendor: bool) -> Result<(), Error> {
-        self.player.purchase_weapon(w, is_vendor)
+        let cost = self.weapon_cost(w, is_vendor);
+
+        if is_vendor {
+            self.vendor_sell(Goods::Weapon(w), cost)?;
+            self.player.set_weapon_by_type(w);
+            return Ok(());
+        }
+
+        self.player.purchase_weapon(w, cost)?;
+        self.stats.gold_spent += cost;
+
+        Ok(())
     }
 
     /// True if the player can afford a lamp
@@ -1520,7 +2965,12 @@ impl Game {
 
     /// Purchase a lamp
     pub fn player_purchase_lamp(&mut self, lamp: bool) -> Result<(), Error> {
-        self.player.purchase_lamp(lamp)
+        let gp = self.player_gp();
+
+        self.player.purchase_lamp(lamp)?;
+        self.stats.gold_spent += gp - self.player_gp();
+
+        Ok(())
     }
 
     /// Return the max number of flares a player can afford
@@ -1530,7 +2980,10 @@ impl Game {
 
     /// Purchase flares
     pub fn player_purchase_flares(&mut self, flares: u32) -> Result<(), Error> {
-        self.player.purchase_flares(flares)
+        self.player.purchase_flares(flares)?;
+        self.stats.gold_spent += flares;
+
+        Ok(())
     }
 
     /// Return true if the player is blind
@@ -1538,6 +2991,11 @@ impl Game {
         self.player.is_blind()
     }
 
+    /// True if the player is poisoned
+    pub fn player_poisoned(&self) -> bool {
+        self.player.poisoned() > 0
+    }
+
     /// True if the player has the Orb of Zot
     pub fn player_has_orb_of_zot(&self) -> bool {
         self.player.has_orb_of_zot()
@@ -1578,6 +3036,18 @@ impl Game {
         self.dungeon.room_at(x, y, z)
     }
 
+    /// Return the edge on one side of a room
+    pub fn dungeon_edge(&self, x: u32, y: u32, z: u32, dir: Direction) -> Edge {
+        self.dungeon.edge(x, y, z, dir)
+    }
+
+    /// Return the edge on one side of the player's room
+    pub fn player_edge(&self, dir: Direction) -> Edge {
+        let (x, y, z) = (*self.player.x(), *self.player.y(), *self.player.z());
+
+        self.dungeon.edge(x, y, z, dir)
+    }
+
     /// Return a mutable reference to the room at a location
     pub fn dungeon_room_at_mut(&mut self, x: u32, y: u32, z: u32) -> &Room {
         self.dungeon.room_at_mut(x, y, z)
@@ -1588,19 +3058,58 @@ impl Game {
         self.player.gender()
     }
 
+    /// Return the difficulty level
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Return the dungeon
+    pub fn dungeon(&self) -> &Dungeon {
+        &self.dungeon
+    }
+
+    /// Return the seed this game was made with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Note the file the castle was read from
+    ///
+    /// The seed can't rebuild a castle like this, only replay its dice.
+    pub fn set_castle_file(&mut self, path: &str) {
+        self.castle_file = Some(String::from(path));
+    }
+
+    /// Return the file the castle was read from, if it was
+    pub fn castle_file(&self) -> Option<&str> {
+        self.castle_file.as_deref()
+    }
+
+    /// Return the name of the generator that made the castle, if one did
+    pub fn generator(&self) -> Option<&'static str> {
+        self.generator
+    }
+
     /// Return number of turns
     pub fn turn(&self) -> &u32 {
         &self.turn
     }
 
     /// Add to turns
+    ///
+    /// The player keeps an eye on their own room, so what they remember of it
+    /// stays up to date.
     pub fn add_turn(&mut self, amount: u32) {
         self.turn += amount;
+
+        if self.room_at_player().discovered {
+            self.discover_room_at_player();
+        }
     }
 
-    /// True if the vendors are angry
-    pub fn vendors_angry(&self) -> bool {
-        self.vendors_angry
+    /// Return what vendors will think of the player once word gets around
+    pub fn reputation(&self) -> i32 {
+        self.reputation.overall()
     }
 
     /// True if the player just bribed the monster in this room
@@ -1641,4 +3150,586 @@ impl Game {
 
         format!("({},{},{})", loc.0 + 1, loc.1 + 1, loc.2 + 1)
     }
+
+    /// Move the player anywhere in the dungeon
+    ///
+    /// This is for debugging use only. Unlike teleporting, this doesn't need
+    /// the runestaff and it never finds the orb of zot.
+    pub fn debug_warp(&mut self, x: u32, y: u32, z: u32) -> Result<(), Error> {
+        if x >= *self.dungeon.xsize() || y >= *self.dungeon.ysize() || z >= *self.dungeon.zsize() {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.player.set_position(x, y, z);
+        self.discover_room_at_player();
+
+        self.player_moved_since_bribe = true;
+
+        Ok(())
+    }
+
+    /// Set one of the player's stats
+    ///
+    /// This is for debugging use only. Returns the new value, which is capped
+    /// at the maximum.
+    pub fn debug_set_stat(&mut self, stat: Stat, val: u32) -> u32 {
+        self.player.set_stat(stat, val)
+    }
+
+    /// Set the player's GP
+    ///
+    /// This is for debugging use only.
+    pub fn debug_set_gp(&mut self, gp: u32) {
+        self.player.set_gp(gp);
+    }
+
+    /// Set the player's keys
+    ///
+    /// This is for debugging use only.
+    pub fn debug_set_keys(&mut self, keys: u32) {
+        let delta = keys as i32 - self.player.keys() as i32;
+
+        self.player.change_keys(delta);
+    }
+
+    /// Add something to the player's room, or empty it out with `Empty`
+    ///
+    /// This is for debugging use only. Emptying out the room with the
+    /// runestaff or the orb of zot warp makes those impossible to find.
+    pub fn debug_add_to_room(&mut self, roomtype: RoomType) {
+        let room = self
+            .dungeon
+            .room_at_mut(*self.player.x(), *self.player.y(), *self.player.z());
+
+        match roomtype {
+            RoomType::Empty => room.make_empty(),
+            r => room.add(r),
+        }
+
+        self.discover_room_at_player();
+
+        // Whatever is here now hasn't been bribed
+        self.player_moved_since_bribe = true;
+    }
+
+    /// Put a curse on the player, or take it off if they already have it
+    ///
+    /// This is for debugging use only. Returns true if the player is now
+    /// cursed.
+    pub fn debug_toggle_curse(&mut self, curse: CurseType) -> bool {
+        if self.player.has_curse(curse) {
+            self.player.remove_curse(curse);
+
+            if curse == CurseType::Lethargy {
+                self.lethargic = false;
+            }
+
+            false
+        } else {
+            self.player.add_curse(curse);
+
+            self.player.has_curse(curse)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: Difficulty) -> Game {
+        let mut game = Game::with_seed(8, 8, 8, difficulty, 1);
+
+        game.player_init(Race::Human);
+        game.player.treasure_add(TreasureType::Silmaril);
+        game.player.add_gp(1000);
+        game.turn = 100;
+
+        game
+    }
+
+    #[test]
+    fn score_counts_loot_only_on_escape() {
+        let mut g = game(Difficulty::Classic);
+
+        let loot = Treasure::treasure_max_value(TreasureType::Silmaril) / 10 + g.player_gp() / 10;
+
+        g.state = GameState::Exit;
+        assert_eq!(g.score(), loot - 100);
+
+        g.player.give_orb_of_zot(true);
+        assert_eq!(g.score(), 5000 + loot - 100);
+
+        g.state = GameState::Dead;
+        assert_eq!(g.score(), 0);
+
+        g.state = GameState::Quit;
+        assert_eq!(g.score(), 0);
+    }
+
+    #[test]
+    fn score_is_scaled_by_difficulty() {
+        let mut easy = game(Difficulty::Easy);
+        let mut nightmare = game(Difficulty::Nightmare);
+
+        easy.state = GameState::Exit;
+        nightmare.state = GameState::Exit;
+
+        // Same loot except the starting gold
+        let easy_base = easy.score() * 100 / Difficulty::Easy.score_percent();
+        let nightmare_base = nightmare.score() * 100 / Difficulty::Nightmare.score_percent();
+
+        assert_eq!(easy_base - nightmare_base, (100 - 20) / 10);
+    }
+
+    #[test]
+    fn undo_rolls_back_the_game_and_its_dice() {
+        let mut g = game(Difficulty::Classic);
+        let gp = g.player_gp();
+
+        g.set_practice(true);
+        g.snapshot();
+
+        g.turn += 1;
+        g.player.add_gp(500);
+
+        let rolls: Vec<u32> = (0..5).map(|_| g.rng.d(1, 100)).collect();
+
+        assert_eq!(g.undo(1), Ok(1));
+        assert_eq!(g.turn, 100);
+        assert_eq!(g.player_gp(), gp);
+        assert_eq!(g.undo_count(), 1);
+
+        let again: Vec<u32> = (0..5).map(|_| g.rng.d(1, 100)).collect();
+
+        assert_eq!(rolls, again);
+    }
+
+    #[test]
+    fn undo_keeps_one_snapshot_a_turn() {
+        let mut g = game(Difficulty::Classic);
+
+        g.set_practice(true);
+        g.snapshot();
+        g.snapshot();
+        g.turn += 1;
+        g.snapshot();
+
+        assert_eq!(g.undo(5), Ok(2));
+        assert_eq!(g.turn, 100);
+        assert_eq!(g.undo(1), Err(Error::NothingToUndo));
+    }
+
+    #[test]
+    fn undo_needs_practice_mode() {
+        let mut g = game(Difficulty::Classic);
+
+        g.snapshot();
+
+        assert_eq!(g.undo(1), Err(Error::NotPracticing));
+    }
+
+    /// Start a fight with a kobold next to the entrance
+    fn fight(difficulty: Difficulty) -> Game {
+        fight_in("M:KOBOLD", difficulty)
+    }
+
+    /// Start a fight in a room next to the entrance, e.g. "M:WOLF+M:WOLF"
+    fn fight_in(room: &str, difficulty: Difficulty) -> Game {
+        let mut g = enter(room, difficulty);
+
+        assert!(matches!(g.room_effect(), Event::Combat(_)));
+
+        g
+    }
+
+    /// Walk into a room, without anything in it happening yet
+    fn enter(room: &str, difficulty: Difficulty) -> Game {
+        let castle = format!("SIZE 4 1 1\nLEVEL 1\nE {} Z R:OGRE\n", room);
+        let dungeon = crate::castle::parse(&castle, difficulty).unwrap();
+
+        let mut g = Game::from_dungeon(dungeon, difficulty, 1);
+
+        g.player_init(Race::Human);
+        g.player.set_stat(Stat::Strength, 18);
+        g.player.set_stat(Stat::Dexterity, 18);
+        g.state = GameState::Move;
+
+        g.debug_warp(1, 0, 0).unwrap();
+
+        g
+    }
+
+    #[test]
+    fn monsters_still_come_after_a_trap() {
+        let mut g = enter("X:SLIME+M:KOBOLD", Difficulty::Hard);
+
+        assert!(matches!(g.room_effect(), Event::SlimeTrap(_)));
+        assert!(matches!(
+            g.room_effect(),
+            Event::Combat(MonsterType::Kobold)
+        ));
+    }
+
+    #[test]
+    fn monsters_guard_what_is_in_the_room() {
+        let mut g = enter("M:KOBOLD+G+F", Difficulty::Classic);
+
+        assert!(matches!(g.room_effect(), Event::Combat(_)));
+
+        // Once they're bribed, everything can be picked up
+        g.player_moved_since_bribe = false;
+
+        assert!(matches!(g.room_effect(), Event::FoundGold(_)));
+        assert!(matches!(g.room_effect(), Event::FoundFlares(_)));
+        assert!(matches!(g.room_effect(), Event::None));
+        assert_eq!(g.dungeon.room_at(1, 0, 0).monsters().count(), 1);
+    }
+
+    #[test]
+    fn calm_vendors_let_the_player_pick_things_up_first() {
+        let mut g = enter("V+G+T:RUBY_RED", Difficulty::Classic);
+
+        for _ in 0..2 {
+            assert!(matches!(
+                g.room_effect(),
+                Event::FoundGold(_) | Event::Treasure(_)
+            ));
+        }
+
+        assert!(matches!(g.room_effect(), Event::Vendor));
+        assert!(!g.dungeon.room_at(1, 0, 0).has(&RoomType::Gold));
+        assert!(g.player_get_treasures().contains(&TreasureType::RubyRed));
+    }
+
+    /// Return the kobold in the room the fight started in
+    fn kobold(g: &Game) -> &Monster {
+        g.dungeon.room_at(1, 0, 0).monster().unwrap()
+    }
+
+    #[test]
+    fn alarms_bring_every_monster_nearby() {
+        let castle = "SIZE 7 1 1\nLEVEL 1\nE Z M:ORC X:ALARM M:KOBOLD+M:KOBOLD V R:OGRE\n";
+        let dungeon = crate::castle::parse(castle, Difficulty::Hard).unwrap();
+
+        let mut g = Game::from_dungeon(dungeon, Difficulty::Hard, 1);
+        g.player_init(Race::Human);
+        g.state = GameState::Move;
+        g.debug_warp(3, 0, 0).unwrap();
+
+        match g.room_effect() {
+            Event::AlarmTrap(monsters) => assert_eq!(
+                monsters,
+                vec![MonsterType::Orc, MonsterType::Kobold, MonsterType::Kobold]
+            ),
+            e => panic!("expected an alarm, got {:?}", e),
+        }
+
+        assert_eq!(g.dungeon.room_at(3, 0, 0).monsters().count(), 3);
+        assert_eq!(g.dungeon.room_at(2, 0, 0).monsters().count(), 0);
+        assert_eq!(g.dungeon.room_at(4, 0, 0).monsters().count(), 0);
+
+        // Vendors and monsters further away stay put
+        assert_eq!(g.dungeon.room_at(5, 0, 0).monsters().count(), 1);
+        assert_eq!(g.dungeon.room_at(6, 0, 0).monsters().count(), 1);
+    }
+
+    #[test]
+    fn retreating_leaves_the_monster_wounded_with_what_it_stole() {
+        let mut g = fight(Difficulty::Classic);
+        let hp = kobold(&g).hp();
+
+        g.currently_fighting[0].take_damage(1);
+        g.currently_fighting[0].add_gold(30);
+
+        g.retreat_dir(Direction::West).unwrap();
+
+        assert!(g.fighting().is_empty());
+        assert_eq!(kobold(&g).hp(), hp - 1);
+        assert_eq!(kobold(&g).gold(), 30);
+        assert_eq!(g.dungeon.room_at(1, 0, 0).monsters().count(), 1);
+    }
+
+    #[test]
+    fn killing_takes_out_the_monster_that_was_fought() {
+        let mut g = fight_in("M:WOLF+M:WOLF+M:WOLF", Difficulty::Hard);
+
+        // Tell the wolves in the room apart by their wounds
+        for (n, m) in g.dungeon.room_at_mut(1, 0, 0).monsters_mut().enumerate() {
+            m.take_damage(n as u32);
+        }
+
+        let hps = |g: &Game| -> Vec<u32> {
+            g.dungeon
+                .room_at(1, 0, 0)
+                .monsters()
+                .map(|m| m.hp())
+                .collect()
+        };
+
+        let full = g.currently_fighting[0].hp();
+
+        g.take_fought_monster(1);
+        g.leave_fight(&[false, true, false]);
+
+        assert_eq!(hps(&g), vec![full, full - 2]);
+
+        g.take_fought_monster(1);
+        g.leave_fight(&[false, true]);
+
+        assert_eq!(hps(&g), vec![full]);
+    }
+
+    #[test]
+    fn monsters_that_fled_stay_gone_when_the_fight_ends() {
+        let mut g = fight_in("M:KOBOLD+V+M:MINOTAUR", Difficulty::Hard);
+
+        assert_eq!(g.fighting().len(), 2);
+
+        // The kobold runs, then the minotaur charges the player out of the
+        // room, all in the same round
+        g.monster_flee(0, Direction::East);
+        g.end_fight((1, 0, 0));
+
+        let types = |x| -> Vec<MonsterType> {
+            g.dungeon
+                .room_at(x, 0, 0)
+                .monsters()
+                .map(|m| m.monster_type())
+                .collect()
+        };
+
+        assert_eq!(types(1), vec![MonsterType::Vendor, MonsterType::Minotaur]);
+        assert_eq!(types(2), vec![MonsterType::Kobold]);
+    }
+
+    #[test]
+    fn calm_vendors_are_left_out_of_the_fight() {
+        let mut g = fight_in("V+M:KOBOLD", Difficulty::Classic);
+
+        assert_eq!(g.fighting().len(), 1);
+
+        g.take_fought_monster(0);
+
+        let room = g.dungeon.room_at(1, 0, 0);
+        assert_eq!(room.monsters().count(), 1);
+        assert_eq!(room.monster().unwrap().monster_type(), MonsterType::Vendor);
+    }
+
+    #[test]
+    fn retreating_leaves_each_monster_as_it_was_fought() {
+        let mut g = fight_in("M:WOLF+M:WOLF", Difficulty::Hard);
+
+        let full = g.currently_fighting[0].hp();
+
+        g.currently_fighting[1].take_damage(1);
+        g.currently_fighting[1].add_gold(5);
+
+        g.retreat_dir(Direction::West).unwrap();
+
+        let wolves: Vec<(u32, u32)> = g
+            .dungeon
+            .room_at(1, 0, 0)
+            .monsters()
+            .map(|m| (m.hp(), m.gold()))
+            .collect();
+
+        assert_eq!(wolves, vec![(full, 0), (full - 1, 5)]);
+    }
+
+    #[test]
+    fn classic_loot_only_rolls_for_gold() {
+        let mut g = fight(Difficulty::Classic);
+        let monster = g.currently_fighting[0].clone();
+
+        let mut expected = g.rng.clone();
+        let gold = expected.d(1, 1000);
+
+        let loot = g.roll_loot(&monster);
+
+        assert_eq!(loot.gold, gold);
+        assert_eq!(g.rng.d(1, 1_000_000), expected.d(1, 1_000_000));
+    }
+
+    #[test]
+    fn monsters_only_pick_up_treasures_on_their_level() {
+        let castle = "SIZE 3 1 2\nLEVEL 1\nE Z R:OGRE\nLEVEL 2\nT:RUBY_RED . .\n";
+        let dungeon = crate::castle::parse(castle, Difficulty::Hard).unwrap();
+
+        let mut g = Game::from_dungeon(dungeon, Difficulty::Hard, 1);
+
+        assert_eq!(g.take_loose_treasure(0), None);
+        assert_eq!(g.take_loose_treasure(1), Some(TreasureType::RubyRed));
+        assert_eq!(g.take_loose_treasure(1), None);
+    }
+
+    /// Start trading with the first of two vendors at either end of a level
+    fn two_vendors(difficulty: Difficulty) -> Game {
+        let castle = "SIZE 5 1 1\nLEVEL 1\nE V Z R:OGRE V\n";
+        let dungeon = crate::castle::parse(castle, difficulty).unwrap();
+
+        let mut g = Game::from_dungeon(dungeon, difficulty, 1);
+
+        g.player_init(Race::Human);
+        g.state = GameState::Move;
+
+        g.debug_warp(1, 0, 0).unwrap();
+        assert!(matches!(g.room_effect(), Event::Vendor));
+
+        g
+    }
+
+    #[test]
+    fn classic_vendors_all_get_angry_and_calm_down_together() {
+        let mut g = two_vendors(Difficulty::Classic);
+
+        assert_eq!(g.vendor_price(1000), 1000);
+
+        g.vendor_attack();
+        assert_eq!(g.reputation(), 0);
+
+        // The other vendor is angry right away
+        g.debug_warp(4, 0, 0).unwrap();
+        assert!(matches!(
+            g.room_effect(),
+            Event::Combat(MonsterType::Vendor)
+        ));
+
+        g.bribe_taken();
+
+        g.debug_warp(1, 0, 0).unwrap();
+        assert!(matches!(g.room_effect(), Event::Vendor));
+        assert_eq!(g.vendor_price(1000), 1000);
+    }
+
+    #[test]
+    fn word_of_an_attack_takes_a_while_to_reach_other_vendors() {
+        let mut g = two_vendors(Difficulty::Hard);
+
+        g.vendor_attack();
+        assert!(Reputation::is_hostile(g.vendor_reputation()));
+
+        g.debug_warp(4, 0, 0).unwrap();
+        assert!(matches!(g.room_effect(), Event::Vendor));
+
+        g.turn += 3 * crate::reputation::GOSSIP_TURNS;
+        assert!(matches!(
+            g.room_effect(),
+            Event::Combat(MonsterType::Vendor)
+        ));
+    }
+
+    #[test]
+    fn bribe_value_depends_on_tastes() {
+        let kobold = Monster::bribe_tastes(MonsterType::Kobold);
+        let wolf = Monster::bribe_tastes(MonsterType::Wolf);
+        let dragon = Monster::bribe_tastes(MonsterType::Dragon);
+
+        let silmaril = Treasure::treasure_max_value(TreasureType::Silmaril);
+        let ruby = Treasure::treasure_max_value(TreasureType::RubyRed);
+
+        assert_eq!(Game::bribe_value(kobold, BribeOffer::Gold(300)), 300);
+        assert_eq!(Game::bribe_value(wolf, BribeOffer::Gold(300)), 0);
+
+        let offer = BribeOffer::Treasure(TreasureType::Silmaril);
+        assert_eq!(Game::bribe_value(dragon, offer), silmaril * 2);
+        assert_eq!(Game::bribe_value(kobold, offer), silmaril);
+
+        let offer = BribeOffer::Treasure(TreasureType::RubyRed);
+        assert_eq!(Game::bribe_value(dragon, offer), ruby);
+    }
+
+    #[test]
+    fn a_bribe_worth_the_price_is_always_taken() {
+        for seed in 0..20 {
+            let mut g = fight(Difficulty::Hard);
+            g.rng = GameRng::new(seed);
+
+            let price = Monster::bribe_tastes(MonsterType::Kobold).price;
+            g.player.add_gp(price as i32);
+            let gp = g.player_gp();
+
+            assert_eq!(g.bribe_offer(BribeOffer::Gold(price)), Ok(true));
+            assert_eq!(g.player_gp(), gp - price);
+            assert!(g.fighting().is_empty());
+            assert_eq!(g.state, GameState::Move);
+        }
+    }
+
+    #[test]
+    fn a_bribe_worth_nothing_is_never_taken() {
+        for seed in 0..20 {
+            let mut g = fight_in("M:KOBOLD+M:WOLF", Difficulty::Hard);
+            g.rng = GameRng::new(seed);
+
+            g.player.add_gp(10000);
+            let gp = g.player_gp();
+
+            // Wolves have no use for gold, however much the kobold likes it
+            assert_eq!(g.bribe_offer(BribeOffer::Gold(10000)), Ok(false));
+            assert_eq!(g.player_gp(), gp);
+            assert_eq!(g.fighting().len(), 2);
+            assert_eq!(g.state, GameState::MonsterAttack);
+        }
+    }
+
+    #[test]
+    fn bribes_have_to_be_the_players_to_give() {
+        let mut g = fight(Difficulty::Hard);
+        let gp = g.player_gp();
+
+        assert_eq!(
+            g.bribe_offer(BribeOffer::Treasure(TreasureType::Silmaril)),
+            Err(Error::BribeNotOwned)
+        );
+        assert_eq!(
+            g.bribe_offer(BribeOffer::Gold(gp + 1)),
+            Err(Error::NotEnoughGP)
+        );
+
+        // Nothing happened, so the player can still choose what to do
+        assert_eq!(g.state, GameState::PlayerAttack);
+        assert!(g.bribe_possible());
+    }
+
+    #[test]
+    fn bribed_monsters_keep_what_they_stole() {
+        let mut g = fight(Difficulty::Classic);
+
+        g.currently_fighting[0].add_gold(12);
+
+        g.bribe_taken();
+
+        assert_eq!(kobold(&g).gold(), 12);
+        assert_eq!(g.dungeon.room_at(1, 0, 0).monsters().count(), 1);
+    }
+
+    #[test]
+    fn poison_kills_in_the_name_of_the_poisoner() {
+        let mut g = game(Difficulty::Hard);
+
+        g.player.set_stat(Stat::Strength, 2);
+        g.player.set_poisoned(3, MonsterType::Wolf);
+
+        assert!(g.poison_effects());
+        assert_eq!(g.player.poisoned(), 2);
+        assert!(g.death_cause().is_none());
+
+        assert!(g.poison_effects());
+        assert_eq!(
+            g.death_cause(),
+            Some(DeathCause::Monster(MonsterType::Wolf))
+        );
+    }
+
+    #[test]
+    fn poison_wears_off() {
+        let mut g = game(Difficulty::Hard);
+
+        g.player.set_poisoned(1, MonsterType::Chimera);
+
+        assert!(g.poison_effects());
+        assert!(!g.poison_effects());
+        assert_eq!(g.player.poisoned_by(), None);
+    }
 }
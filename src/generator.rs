@@ -0,0 +1,856 @@
+extern crate rand;
+
+use self::rand::distributions::{Distribution, WeightedIndex};
+use self::rand::seq::SliceRandom;
+use self::rand::Rng;
+
+use crate::curse::Curse;
+use crate::difficulty::Difficulty;
+use crate::dungeon::{Dungeon, Edge};
+use crate::game::Direction;
+use crate::monster::{Monster, MonsterType};
+use crate::rng::GameRng;
+use crate::room::{Room, RoomType};
+use crate::treasure::Treasure;
+
+/// Monsters that can be placed, weakest first (not counting Vendors)
+pub const MONSTERS: [MonsterType; 12] = [
+    MonsterType::Kobold,
+    MonsterType::Orc,
+    MonsterType::Wolf,
+    MonsterType::Goblin,
+    MonsterType::Ogre,
+    MonsterType::Troll,
+    MonsterType::Bear,
+    MonsterType::Minotaur,
+    MonsterType::Gargoyle,
+    MonsterType::Chimera,
+    MonsterType::Balrog,
+    MonsterType::Dragon,
+];
+
+/// Monsters that can run in packs
+const PACK_MONSTERS: [MonsterType; 4] = [
+    MonsterType::Kobold,
+    MonsterType::Orc,
+    MonsterType::Wolf,
+    MonsterType::Goblin,
+];
+
+/// Something that can lay out the rooms of a dungeon
+///
+/// Every dungeon needs exactly one entrance on the first level, one orb of zot
+/// warp, one monster with the runestaff, and stairs down that line up with
+/// stairs up on the next level. Use the generator passed in for randomness so
+/// dungeons can be made again from their seed.
+pub trait DungeonGenerator {
+    /// Return the generator's name, e.g. "VAULTS"
+    fn name(&self) -> &'static str;
+
+    /// Return the rooms for each level, each in row-major order
+    fn levels(
+        &self,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Vec<Vec<Room>>;
+
+    /// Put walls and doors between the rooms
+    ///
+    /// By default every room opens onto all of its neighbors. Every room on a
+    /// level must still be reachable from every other without going through
+    /// locked or secret doors.
+    fn walls(&self, _rng: &mut GameRng, _dungeon: &mut Dungeon) {}
+}
+
+/// The original game's dungeon
+pub struct ClassicGenerator;
+
+impl DungeonGenerator for ClassicGenerator {
+    fn name(&self) -> &'static str {
+        "CLASSIC"
+    }
+
+    fn levels(
+        &self,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Vec<Vec<Room>> {
+        ClassicGenerator::build(rng, xsize, ysize, zsize, difficulty, None)
+    }
+}
+
+impl ClassicGenerator {
+    /// Lay out the dungeon
+    ///
+    /// With spawn weights, monsters are picked by level instead of every
+    /// level getting one of each.
+    fn build(
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+        weights: Option<&SpawnWeights>,
+    ) -> Vec<Vec<Room>> {
+        let mut levels: Vec<Vec<Room>> = Vec::new();
+
+        let area = xsize * ysize;
+
+        let orb_of_zot_level = rng.gen_range(0..zsize);
+
+        // When monsters get tougher with depth, keep the runestaff in the top
+        // half of the castle so it can be had before the worst of them
+        let runestaff_level = match weights {
+            None => rng.gen_range(0..zsize),
+            Some(_) => rng.gen_range(0..zsize.div_ceil(2)),
+        };
+
+        // Add all necessary elements to the level
+        for z in 0..zsize {
+            let mut this_level = Vec::new();
+
+            ClassicGenerator::place_ent_stairs(&mut this_level, z, zsize, area);
+            ClassicGenerator::place_items(&mut this_level, orb_of_zot_level, z, area);
+            ClassicGenerator::place_traps(rng, &mut this_level, difficulty);
+            ClassicGenerator::place_monsters_vendors(
+                rng,
+                &mut this_level,
+                z,
+                area,
+                runestaff_level,
+                difficulty,
+                weights.map(|w| w.level(z)),
+            );
+            ClassicGenerator::place_packs(rng, &mut this_level, difficulty);
+
+            levels.push(this_level);
+        }
+
+        // Curses and treasures
+        ClassicGenerator::place_curse_treasure(rng, &mut levels, zsize, difficulty);
+
+        // Run through the levels, padding them with empty rooms, shuffling
+        // them, and moving certain rooms to their proper positions.
+
+        for l in &mut levels {
+            // Fill the rest with empty
+            while l.len() < area as usize {
+                l.push(Room::default());
+            }
+
+            // Shuffle the level
+            l.shuffle(rng);
+        }
+
+        // Fix up the stairs and entrance
+        ClassicGenerator::place_fixup(&mut levels, xsize, ysize, zsize, area);
+
+        levels
+    }
+
+    /// Place the entryway and the stairs
+    fn place_ent_stairs(this_level: &mut Vec<Room>, z: u32, zsize: u32, area: u32) {
+        let stair_count = area / 32; // 2 in 8x8
+
+        // Entrance
+        if z == 0 {
+            let mut entrance = Room::new(RoomType::Entrance);
+            entrance.set_discovered(true);
+
+            this_level.push(entrance);
+        }
+
+        // Stairs down
+        if z < zsize - 1 {
+            for _ in 0..stair_count {
+                this_level.push(Room::new(RoomType::StairsDown));
+            }
+        }
+
+        // Stairs up
+        if z > 0 {
+            for _ in 0..stair_count {
+                this_level.push(Room::new(RoomType::StairsUp));
+            }
+        }
+    }
+
+    /// Place the items in the dungeon
+    fn place_items(this_level: &mut Vec<Room>, orb_of_zot_level: u32, z: u32, area: u32) {
+        let item_count = area / 21; // 3 in 8x8
+
+        // Items
+        for i in 0..item_count {
+            let orb_of_zot_warp = i == 0 && z == orb_of_zot_level;
+
+            this_level.push(Room::new(RoomType::Gold));
+            this_level.push(Room::new(RoomType::Pool));
+            this_level.push(Room::new(RoomType::Chest));
+            this_level.push(Room::new(RoomType::Flares));
+            this_level.push(Room::new(RoomType::Warp(orb_of_zot_warp)));
+            this_level.push(Room::new(RoomType::Sinkhole));
+            this_level.push(Room::new(RoomType::CrystalOrb));
+            this_level.push(Room::new(RoomType::Book));
+        }
+    }
+
+    /// Place traps, which the original game didn't have
+    fn place_traps(rng: &mut GameRng, this_level: &mut Vec<Room>, difficulty: Difficulty) {
+        let traps = [
+            RoomType::PitTrap,
+            RoomType::DartTrap,
+            RoomType::AlarmTrap,
+            RoomType::SlimeTrap,
+        ];
+
+        for _ in 0..difficulty.trap_count() {
+            this_level.push(Room::new(traps[rng.gen_range(0..traps.len())].clone()));
+        }
+    }
+
+    /// Place monsters and vendors in the dungeon
+    fn place_monsters_vendors(
+        rng: &mut GameRng,
+        this_level: &mut Vec<Room>,
+        z: u32,
+        area: u32,
+        runestaff_level: u32,
+        difficulty: Difficulty,
+        weights: Option<&[u32; 12]>,
+    ) {
+        let vendor_count = area / 21; // 3 in 8x8
+        let monster_count = area / 5; // 12 in 8x8
+
+        // Monsters
+        let num_monsters = MONSTERS.len();
+
+        let weighted = weights.and_then(|w| WeightedIndex::new(w.iter()).ok());
+
+        let monster_with_runestaff = rng.gen_range(0..monster_count) as usize;
+
+        for i in 0..monster_count as usize {
+            let has_runestaff = i == monster_with_runestaff && z == runestaff_level;
+
+            let m_num = match weighted {
+                Some(ref w) => w.sample(rng),
+                None => i % num_monsters,
+            };
+
+            this_level.push(Room::new(RoomType::Monster(Monster::new(
+                MONSTERS[m_num],
+                has_runestaff,
+                difficulty,
+            ))));
+        }
+
+        // Vendors
+        for _ in 0..vendor_count {
+            this_level.push(Room::new(RoomType::Monster(Monster::new(
+                MonsterType::Vendor,
+                false,
+                difficulty,
+            ))));
+        }
+    }
+
+    /// Turn some of the weaker monsters into packs of two or three, which the
+    /// original game didn't have
+    fn place_packs(rng: &mut GameRng, this_level: &mut [Room], difficulty: Difficulty) {
+        let leaders: Vec<usize> = (0..this_level.len())
+            .filter(|i| match this_level[*i].monster() {
+                Some(m) => PACK_MONSTERS.contains(&m.monster_type()) && !m.has_runestaff(),
+                None => false,
+            })
+            .collect();
+
+        for &i in leaders.choose_multiple(rng, difficulty.pack_count() as usize) {
+            let monster_type = match this_level[i].monster() {
+                Some(m) => m.monster_type(),
+                None => continue,
+            };
+
+            for _ in 0..rng.gen_range(1..=2) {
+                this_level[i].add(RoomType::Monster(Monster::new(
+                    monster_type,
+                    false,
+                    difficulty,
+                )));
+            }
+        }
+    }
+
+    /// Place curses and treasures
+    fn place_curse_treasure(
+        rng: &mut GameRng,
+        levels: &mut [Vec<Room>],
+        zsize: u32,
+        difficulty: Difficulty,
+    ) {
+        // Add curse rooms, repeating curses if there are more rooms than curses
+        for i in 0..difficulty.curse_count() {
+            let curse_level = rng.gen_range(0..zsize) as usize;
+
+            let curse = Curse::get_curse_by_id(i % crate::curse::CURSE_COUNT);
+
+            let mut room = Room::default();
+            room.set_curse(curse);
+
+            levels[curse_level].push(room);
+        }
+
+        // Add treasures
+        for i in 0..crate::treasure::TREASURE_COUNT {
+            let treasure_level = rng.gen_range(0..zsize) as usize;
+
+            levels[treasure_level].push(Room::new(RoomType::Treasure(Treasure::new(i))))
+        }
+    }
+
+    /// Fix stairs and entrance on levels
+    fn place_fixup(levels: &mut [Vec<Room>], xsize: u32, ysize: u32, zsize: u32, area: u32) {
+        let entrance_x = (xsize - 1) / 2;
+
+        for z in 0..zsize as usize {
+            // Fix up the entrance
+            for y in 0..ysize as usize {
+                for x in 0..xsize as usize {
+                    let i = y * xsize as usize + x;
+
+                    // Swap the entrance
+                    if levels[z][i].has(&RoomType::Entrance) {
+                        let i2 = (/*0 * xsize + */entrance_x) as usize;
+
+                        levels[z].swap(i, i2);
+                    }
+                }
+            }
+
+            // Fix up the stairs up
+            if z > 0 {
+                let mut downs = Vec::new();
+                let mut ups = Vec::new();
+
+                for (i, room) in levels[z - 1].iter().enumerate().take(area as usize) {
+                    if room.has(&RoomType::StairsDown) {
+                        downs.push(i);
+                    }
+                }
+
+                for (i, room) in levels[z].iter().enumerate().take(area as usize) {
+                    if room.has(&RoomType::StairsUp) {
+                        ups.push(i);
+                    }
+                }
+
+                // Leave stairs that already line up alone, or the swaps below
+                // can move them out of place again
+                let lined_up: Vec<usize> =
+                    ups.iter().copied().filter(|i| downs.contains(i)).collect();
+
+                ups.retain(|i| !lined_up.contains(i));
+                downs.retain(|i| !lined_up.contains(i));
+
+                while let Some(up_i) = ups.pop() {
+                    let down_i = downs.pop().unwrap();
+
+                    levels[z].swap(up_i, down_i);
+                }
+            }
+        }
+    }
+}
+
+/// How likely each monster is to turn up on each level
+///
+/// Each level has a weight for every monster in `MONSTERS`. A level with all
+/// zero weights gets one of each monster, like the classic dungeon. Levels
+/// past the end use the last level's weights, and no levels at all is the
+/// same as one level of zeros.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnWeights {
+    levels: Vec<[u32; 12]>,
+}
+
+impl SpawnWeights {
+    /// Make spawn weights from a list of levels, top first
+    pub fn new(mut levels: Vec<[u32; 12]>) -> SpawnWeights {
+        if levels.is_empty() {
+            levels.push([0; 12]);
+        }
+
+        SpawnWeights { levels }
+    }
+
+    /// Weights where monsters get tougher the deeper you go
+    ///
+    /// Kobolds and Orcs are most common at the top, and Balrogs and Dragons at
+    /// the bottom.
+    pub fn by_depth(zsize: u32) -> SpawnWeights {
+        let mut levels = Vec::new();
+
+        for z in 0..zsize {
+            // Which monster this level centers on, rounded to the nearest
+            let target = if zsize > 1 {
+                let strongest = MONSTERS.len() as u32 - 1;
+
+                (z * strongest + (zsize - 1) / 2) / (zsize - 1)
+            } else {
+                0
+            };
+
+            let mut w = [0; 12];
+
+            for (i, w) in w.iter_mut().enumerate() {
+                let distance = (i as i32 - target as i32).unsigned_abs();
+                *w = 12u32.saturating_sub(3 * distance);
+            }
+
+            levels.push(w);
+        }
+
+        SpawnWeights::new(levels)
+    }
+
+    /// Return the weights for a level
+    pub fn level(&self, z: u32) -> &[u32; 12] {
+        let i = std::cmp::min(z as usize, self.levels.len() - 1);
+
+        &self.levels[i]
+    }
+
+    /// Set the weights for a level
+    pub fn set_level(&mut self, z: u32, weights: [u32; 12]) {
+        let z = z as usize;
+
+        while self.levels.len() <= z {
+            let last = self.levels[self.levels.len() - 1];
+            self.levels.push(last);
+        }
+
+        self.levels[z] = weights;
+    }
+}
+
+/// The classic dungeon, but monsters are picked by how deep they are
+pub struct DepthGenerator {
+    pub weights: SpawnWeights,
+}
+
+impl DepthGenerator {
+    /// Make a generator with the standard weights for a castle this deep
+    pub fn new(zsize: u32) -> DepthGenerator {
+        DepthGenerator {
+            weights: SpawnWeights::by_depth(zsize),
+        }
+    }
+}
+
+impl DungeonGenerator for DepthGenerator {
+    fn name(&self) -> &'static str {
+        "DEPTH"
+    }
+
+    fn levels(
+        &self,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Vec<Vec<Room>> {
+        ClassicGenerator::build(rng, xsize, ysize, zsize, difficulty, Some(&self.weights))
+    }
+}
+
+/// The classic dungeon, but with each level's treasures gathered into a vault
+/// surrounded by monsters
+///
+/// The middle of each vault is locked, as is the orb of zot warp. A monster
+/// somewhere on the same level has the key, even if one has to be added to
+/// carry it.
+pub struct VaultGenerator;
+
+impl DungeonGenerator for VaultGenerator {
+    fn name(&self) -> &'static str {
+        "VAULTS"
+    }
+
+    fn levels(
+        &self,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Vec<Vec<Room>> {
+        let mut levels = ClassicGenerator.levels(rng, xsize, ysize, zsize, difficulty);
+
+        for l in &mut levels {
+            VaultGenerator::build_vault(l, xsize, ysize, rng, difficulty);
+        }
+
+        levels
+    }
+}
+
+impl VaultGenerator {
+    /// Move a level's treasures together, with monsters around them
+    fn build_vault(
+        level: &mut [Room],
+        xsize: u32,
+        ysize: u32,
+        rng: &mut GameRng,
+        difficulty: Difficulty,
+    ) {
+        let is_treasure = |r: &Room| matches!(r.room_type(), RoomType::Treasure(_));
+
+        let is_guard = |r: &Room| match r.monster() {
+            Some(m) => m.monster_type() != MonsterType::Vendor,
+            None => false,
+        };
+
+        let treasure_count = level.iter().filter(|r| is_treasure(r)).count();
+
+        if treasure_count == 0 {
+            return;
+        }
+
+        // Stairs and the entrance have to stay where they are, so everything
+        // else is sorted by how close it is to the middle of the vault. The
+        // castle wraps around, so distances do too.
+        let cx = rng.gen_range(0..xsize);
+        let cy = rng.gen_range(0..ysize);
+
+        let distance = |i: usize| {
+            let (x, y) = (i as u32 % xsize, i as u32 / xsize);
+
+            Dungeon::distance(xsize, ysize, (x, y), (cx, cy))
+        };
+
+        let mut cells: Vec<usize> = (0..level.len())
+            .filter(|i| {
+                !matches!(
+                    level[*i].room_type(),
+                    RoomType::Entrance | RoomType::StairsDown | RoomType::StairsUp
+                )
+            })
+            .collect();
+
+        cells.sort_by_key(|i| distance(*i));
+
+        // The treasures go in the closest cells, then the guards around them
+        let guard_count = std::cmp::min(
+            treasure_count + 2,
+            level.iter().filter(|r| is_guard(r)).count(),
+        );
+
+        let (vault, rest) = cells.split_at(treasure_count);
+        let (guards, rest) = rest.split_at(std::cmp::min(guard_count, rest.len()));
+
+        let outside_vault: Vec<usize> = guards.iter().chain(rest).copied().collect();
+
+        VaultGenerator::gather(level, vault, &outside_vault, is_treasure);
+        VaultGenerator::gather(level, guards, rest, is_guard);
+
+        // Lock up the orb of zot warp if it's on this level, or else the
+        // treasure in the middle of the vault
+        let locked = level
+            .iter()
+            .position(|r| r.has(&RoomType::Warp(true)))
+            .unwrap_or(vault[0]);
+
+        level[locked].lock();
+
+        // Give the key to a monster away from the vault if there is one, so
+        // the player has to go looking for it
+        let mut holders: Vec<usize> = rest
+            .iter()
+            .copied()
+            .filter(|i| is_guard(&level[*i]))
+            .collect();
+
+        if holders.is_empty() {
+            holders = guards
+                .iter()
+                .copied()
+                .filter(|i| is_guard(&level[*i]))
+                .collect();
+        }
+
+        if let Some(&i) = holders.choose(rng) {
+            if let Some(m) = level[i].monster_mut() {
+                m.give_key();
+            }
+
+            return;
+        }
+
+        // With nobody to carry the key, a new monster brings it into an empty
+        // room, away from the vault if possible
+        let is_empty = |i: &usize| *level[*i].room_type() == RoomType::Empty;
+
+        let empty = match rest.iter().copied().filter(is_empty).collect::<Vec<_>>() {
+            e if !e.is_empty() => e,
+            _ => guards.iter().copied().filter(is_empty).collect(),
+        };
+
+        match empty.choose(rng) {
+            Some(&i) => {
+                let mut monster = Monster::new(*MONSTERS.choose(rng).unwrap(), false, difficulty);
+                monster.give_key();
+
+                level[i] = Room::new(RoomType::Monster(monster));
+            }
+            // Nowhere to put a key, so nothing can be locked
+            None => level[locked].unlock(),
+        }
+    }
+
+    /// Swap rooms from `from` into `into` until every cell of `into` has a
+    /// room matching `wanted`, or there are no more to move
+    fn gather(level: &mut [Room], into: &[usize], from: &[usize], wanted: impl Fn(&Room) -> bool) {
+        let mut from: Vec<usize> = from
+            .iter()
+            .copied()
+            .filter(|i| wanted(&level[*i]))
+            .collect();
+
+        for i in into {
+            if wanted(&level[*i]) {
+                continue;
+            }
+
+            match from.pop() {
+                Some(j) => level.swap(*i, j),
+                None => return,
+            }
+        }
+    }
+}
+
+/// Another generator's dungeon, with each level made into a maze
+///
+/// The passages of the maze are open or have doors. Some of the other walls
+/// are knocked through to make loops, and some of those get locked or secret
+/// doors instead.
+pub struct MazeGenerator {
+    pub rooms: Box<dyn DungeonGenerator>,
+}
+
+impl MazeGenerator {
+    /// Make a maze out of the rooms from another generator
+    pub fn new(rooms: Box<dyn DungeonGenerator>) -> MazeGenerator {
+        MazeGenerator { rooms }
+    }
+
+    /// Return the room next to another, wrapping around the level
+    fn neighbor(x: u32, y: u32, dir: Direction, xsize: u32, ysize: u32) -> (u32, u32) {
+        match dir {
+            Direction::North => (x, (y + ysize - 1) % ysize),
+            Direction::South => (x, (y + 1) % ysize),
+            Direction::West => ((x + xsize - 1) % xsize, y),
+            Direction::East => ((x + 1) % xsize, y),
+        }
+    }
+}
+
+impl DungeonGenerator for MazeGenerator {
+    fn name(&self) -> &'static str {
+        "MAZE"
+    }
+
+    fn levels(
+        &self,
+        rng: &mut GameRng,
+        xsize: u32,
+        ysize: u32,
+        zsize: u32,
+        difficulty: Difficulty,
+    ) -> Vec<Vec<Room>> {
+        self.rooms.levels(rng, xsize, ysize, zsize, difficulty)
+    }
+
+    fn walls(&self, rng: &mut GameRng, dungeon: &mut Dungeon) {
+        let (xsize, ysize, zsize) = (*dungeon.xsize(), *dungeon.ysize(), *dungeon.zsize());
+
+        let dirs = [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+        ];
+
+        for z in 0..zsize {
+            // Start with every room walled in
+            for y in 0..ysize {
+                for x in 0..xsize {
+                    dungeon.set_edge(x, y, z, Direction::North, Edge::Wall);
+                    dungeon.set_edge(x, y, z, Direction::West, Edge::Wall);
+                }
+            }
+
+            // Carve the passages with a random depth-first walk, which
+            // reaches every room exactly once
+            let mut visited = vec![false; (xsize * ysize) as usize];
+            let mut stack = vec![(dungeon.entrance_x(), dungeon.entrance_y())];
+
+            visited[(dungeon.entrance_y() * xsize + dungeon.entrance_x()) as usize] = true;
+
+            while let Some(&(x, y)) = stack.last() {
+                let next: Vec<(Direction, u32, u32)> = dirs
+                    .iter()
+                    .map(|d| {
+                        let (nx, ny) = MazeGenerator::neighbor(x, y, *d, xsize, ysize);
+                        (*d, nx, ny)
+                    })
+                    .filter(|(_, nx, ny)| !visited[(ny * xsize + nx) as usize])
+                    .collect();
+
+                match next.choose(rng) {
+                    Some(&(d, nx, ny)) => {
+                        let edge = if rng.gen_range(0..4) == 0 {
+                            Edge::Door
+                        } else {
+                            Edge::Open
+                        };
+
+                        dungeon.set_edge(x, y, z, d, edge);
+
+                        visited[(ny * xsize + nx) as usize] = true;
+                        stack.push((nx, ny));
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+
+            // Knock through some of the walls that are left
+            for y in 0..ysize {
+                for x in 0..xsize {
+                    for d in [Direction::North, Direction::West] {
+                        if dungeon.edge(x, y, z, d) != Edge::Wall {
+                            continue;
+                        }
+
+                        let edge = match rng.gen_range(0..32) {
+                            0..=3 => Edge::Open,
+                            4..=5 => Edge::SecretDoor,
+                            6 => Edge::LockedDoor,
+                            _ => Edge::Wall,
+                        };
+
+                        dungeon.set_edge(x, y, z, d, edge);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_weights_with_no_levels_are_classic() {
+        let weights = SpawnWeights::new(vec![]);
+
+        assert_eq!(weights.level(0), &[0; 12]);
+        assert_eq!(weights.level(7), &[0; 12]);
+        assert_eq!(SpawnWeights::by_depth(0), weights);
+    }
+
+    #[test]
+    fn only_hard_castles_have_traps() {
+        let traps = |difficulty| {
+            let mut rng = GameRng::new(5);
+            let dungeon = Dungeon::generate(&ClassicGenerator, &mut rng, 8, 8, 8, difficulty);
+
+            let mut count = 0;
+
+            for z in 0..8 {
+                for y in 0..8 {
+                    for x in 0..8 {
+                        if dungeon.room_at(x, y, z).is_trap() {
+                            count += 1;
+                        }
+                    }
+                }
+            }
+
+            count
+        };
+
+        assert_eq!(traps(Difficulty::Easy), 0);
+        assert_eq!(traps(Difficulty::Classic), 0);
+        assert!(traps(Difficulty::Hard) > 0);
+    }
+
+    #[test]
+    fn vaults_without_guards_still_have_a_key() {
+        let vendor = || {
+            Room::new(RoomType::Monster(Monster::new(
+                MonsterType::Vendor,
+                false,
+                Difficulty::Hard,
+            )))
+        };
+
+        let level = |empty: bool| {
+            let mut level = vec![
+                Room::new(RoomType::Entrance),
+                Room::new(RoomType::Treasure(Treasure::new(0))),
+                vendor(),
+            ];
+
+            if empty {
+                level.push(Room::new(RoomType::Empty));
+            } else {
+                level.push(vendor());
+            }
+
+            level
+        };
+
+        // With nowhere else to go, the key holder is put in the empty room
+        let mut rooms = level(true);
+        VaultGenerator::build_vault(&mut rooms, 2, 2, &mut GameRng::new(1), Difficulty::Hard);
+
+        assert_eq!(rooms.iter().filter(|r| r.is_locked()).count(), 1);
+
+        let holders: Vec<&Monster> = rooms
+            .iter()
+            .flat_map(|r| r.monsters())
+            .filter(|m| m.has_key())
+            .collect();
+
+        assert_eq!(holders.len(), 1);
+        assert_ne!(holders[0].monster_type(), MonsterType::Vendor);
+
+        // With no room for one either, the vault is left open
+        let mut rooms = level(false);
+        VaultGenerator::build_vault(&mut rooms, 2, 2, &mut GameRng::new(1), Difficulty::Hard);
+
+        assert!(rooms.iter().all(|r| !r.is_locked()));
+    }
+
+    #[test]
+    fn spawn_weights_past_the_end_repeat_the_last_level() {
+        let mut weights = SpawnWeights::new(vec![[1; 12], [2; 12]]);
+
+        assert_eq!(weights.level(5), &[2; 12]);
+
+        weights.set_level(4, [3; 12]);
+
+        assert_eq!(weights.level(3), &[2; 12]);
+        assert_eq!(weights.level(4), &[3; 12]);
+        assert_eq!(weights.level(9), &[3; 12]);
+    }
+}
@@ -0,0 +1,267 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::Outcome;
+use crate::player::{Gender, Race};
+
+/// Maximum number of entries kept in the table
+pub const HIGH_SCORE_COUNT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighScore {
+    pub name: String,
+    pub race: Race,
+    pub gender: Gender,
+    pub outcome: Outcome,
+    pub score: u32,
+    pub turns: u32,
+    pub date: String,
+}
+
+#[derive(Debug, Default)]
+pub struct HighScoreTable {
+    scores: Vec<HighScore>,
+}
+
+impl HighScoreTable {
+    pub fn new() -> HighScoreTable {
+        Default::default()
+    }
+
+    /// Return the default location of the high score file
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|d| d.join("scores.txt"))
+    }
+
+    /// Load a table from a file
+    ///
+    /// A missing file is an empty table. Lines that can't be parsed are
+    /// skipped.
+    pub fn load(path: &Path) -> io::Result<HighScoreTable> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut table = HighScoreTable::new();
+
+        for line in text.lines() {
+            if let Some(score) = HighScoreTable::parse_line(line) {
+                table.scores.push(score);
+            }
+        }
+
+        table.sort();
+
+        Ok(table)
+    }
+
+    /// Save the table to a file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut text = String::new();
+
+        for s in &self.scores {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                s.score,
+                s.name,
+                s.race.name(),
+                s.gender.name(),
+                s.outcome.name(),
+                s.turns,
+                s.date
+            ));
+        }
+
+        fs::write(path, text)
+    }
+
+    /// Parse a tab-separated line from the score file
+    fn parse_line(line: &str) -> Option<HighScore> {
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        if fields.len() != 7 {
+            return None;
+        }
+
+        Some(HighScore {
+            score: fields[0].parse().ok()?,
+            name: String::from(fields[1]),
+            race: Race::from_name(fields[2])?,
+            gender: Gender::from_name(fields[3])?,
+            outcome: Outcome::from_name(fields[4])?,
+            turns: fields[5].parse().ok()?,
+            date: String::from(fields[6]),
+        })
+    }
+
+    /// Sort by score, highest first, and drop anything off the end
+    fn sort(&mut self) {
+        self.scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        self.scores.truncate(HIGH_SCORE_COUNT);
+    }
+
+    /// True if a score would make it onto the table
+    ///
+    /// Nothing doesn't count, even when there's room.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.scores.len() < HIGH_SCORE_COUNT || self.scores.iter().any(|s| score > s.score))
+    }
+
+    /// Add a score to the table
+    ///
+    /// Returns the 0-based rank, or None if it didn't make the table
+    pub fn add(&mut self, mut score: HighScore) -> Option<usize> {
+        if !self.qualifies(score.score) {
+            return None;
+        }
+
+        // Tabs and newlines would break the file format
+        score.name = score.name.replace(['\t', '\n'], " ");
+
+        let rank = self
+            .scores
+            .iter()
+            .position(|s| score.score > s.score)
+            .unwrap_or(self.scores.len());
+
+        self.scores.insert(rank, score);
+        self.sort();
+
+        Some(rank)
+    }
+
+    /// Return the scores, highest first
+    pub fn scores(&self) -> &[HighScore] {
+        &self.scores
+    }
+}
+
+/// Return the directory where the game keeps its files
+///
+/// This is `$XDG_DATA_HOME/wizardscastle`, falling back to
+/// `%APPDATA%\wizardscastle` on Windows, and then to
+/// `~/.local/share/wizardscastle`.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_DATA_HOME") {
+        return Some(PathBuf::from(dir).join("wizardscastle"));
+    }
+
+    if let Some(dir) = std::env::var_os("APPDATA") {
+        return Some(PathBuf::from(dir).join("wizardscastle"));
+    }
+
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("wizardscastle")
+    })
+}
+
+/// Return today's date (UTC) as YYYY-MM-DD
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // Convert days since the epoch to a civil date
+    let z = (secs / 86400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u32) -> HighScore {
+        HighScore {
+            name: String::from("BEEJ"),
+            race: Race::Elf,
+            gender: Gender::Female,
+            outcome: Outcome::Won,
+            score,
+            turns: 100,
+            date: String::from("2021-01-01"),
+        }
+    }
+
+    #[test]
+    fn parse_line_reads_a_saved_line() {
+        let score = HighScoreTable::parse_line("500\tBEEJ\tELF\tFEMALE\tWON\t100\t2021-01-01");
+
+        assert_eq!(score, Some(entry(500)));
+    }
+
+    #[test]
+    fn parse_line_rejects_bad_lines() {
+        assert_eq!(HighScoreTable::parse_line(""), None);
+        assert_eq!(
+            HighScoreTable::parse_line("500\tBEEJ\tELF\tFEMALE\tWON\t100"),
+            None
+        );
+        assert_eq!(
+            HighScoreTable::parse_line("lots\tBEEJ\tELF\tFEMALE\tWON\t100\t2021-01-01"),
+            None
+        );
+        assert_eq!(
+            HighScoreTable::parse_line("500\tBEEJ\tORC\tFEMALE\tWON\t100\t2021-01-01"),
+            None
+        );
+    }
+
+    #[test]
+    fn add_ranks_and_keeps_the_top_ten() {
+        let mut table = HighScoreTable::new();
+
+        for s in 1..=HIGH_SCORE_COUNT as u32 {
+            table.add(entry(s * 10));
+        }
+
+        assert!(!table.qualifies(10));
+        assert_eq!(table.add(entry(5)), None);
+        assert_eq!(table.add(entry(55)), Some(5));
+        assert_eq!(table.scores().len(), HIGH_SCORE_COUNT);
+        assert_eq!(table.scores()[0].score, 100);
+        assert_eq!(table.scores()[HIGH_SCORE_COUNT - 1].score, 20);
+    }
+
+    #[test]
+    fn zero_never_qualifies() {
+        let mut table = HighScoreTable::new();
+
+        assert!(!table.qualifies(0));
+        assert_eq!(table.add(entry(0)), None);
+        assert!(table.qualifies(1));
+    }
+
+    #[test]
+    fn add_keeps_names_on_one_field() {
+        let mut table = HighScoreTable::new();
+
+        let mut score = entry(10);
+        score.name = String::from("BE\tE\nJ");
+
+        table.add(score);
+
+        assert_eq!(table.scores()[0].name, "BE E J");
+    }
+}
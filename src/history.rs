@@ -0,0 +1,371 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::armor::ArmorType;
+use crate::difficulty::Difficulty;
+use crate::game::{DeathCause, Game, Outcome};
+use crate::highscore::{self, data_dir};
+use crate::monster::MonsterType;
+use crate::player::{Gender, Race};
+use crate::treasure::TreasureType;
+use crate::weapon::WeaponType;
+
+/// A record of one finished game
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub date: String,
+    pub seed: u64,
+    pub castle: Option<String>, // The castle file, if the seed didn't make the castle
+    pub generator: Option<String>, // The generator, if the seed made the castle
+    pub difficulty: Difficulty,
+    pub race: Race,
+    pub gender: Gender,
+    pub outcome: Outcome,
+    pub death_cause: Option<DeathCause>,
+    pub level: u32,
+    pub turns: u32,
+    pub score: u32,
+    pub gp: u32,
+    pub weapon: WeaponType,
+    pub armor: ArmorType,
+    pub treasures: Vec<TreasureType>,
+}
+
+impl RunRecord {
+    /// Make a record from a finished game
+    ///
+    /// Returns None if the game isn't over.
+    pub fn from_game(game: &Game) -> Option<RunRecord> {
+        Some(RunRecord {
+            date: highscore::today(),
+            seed: game.seed(),
+            // Tabs and newlines would break the file format
+            castle: game.castle_file().map(|c| c.replace(['\t', '\n'], " ")),
+            generator: game.generator().map(String::from),
+            difficulty: game.difficulty(),
+            race: *game.player_race(),
+            gender: *game.player_gender(),
+            outcome: game.outcome()?,
+            death_cause: game.death_cause(),
+            level: game.player_z() + 1,
+            turns: *game.turn(),
+            score: game.score(),
+            gp: game.player_gp(),
+            weapon: game.player_weapon_type(),
+            armor: game.player_armor_type(),
+            treasures: game.player_get_treasures(),
+        })
+    }
+
+    /// Return an epitaph for this run
+    ///
+    /// E.g. "SLAIN BY A DRAGON ON LEVEL 6 AFTER 412 TURNS"
+    pub fn epitaph(&self) -> String {
+        let what = match self.outcome {
+            Outcome::Won => {
+                return format!("ESCAPED WITH THE ORB OF ZOT AFTER {} TURNS", self.turns);
+            }
+            Outcome::Escaped => {
+                return format!("FLED THE CASTLE EMPTY-HANDED AFTER {} TURNS", self.turns);
+            }
+            Outcome::Quit => String::from("GAVE UP"),
+            Outcome::Died => match self.death_cause {
+                Some(DeathCause::Monster(m)) => {
+                    format!("SLAIN BY {} {}", m.article(), m.name())
+                }
+                Some(DeathCause::ExplodingChest) => String::from("BLOWN UP BY A CHEST"),
+                Some(DeathCause::Fireball) => String::from("BURNED OUT CASTING A FIREBALL"),
+                Some(DeathCause::Deathspell) => String::from("UNDONE BY THEIR OWN DEATHSPELL"),
+                Some(DeathCause::Web) => String::from("DRAINED BY A WEB SPELL"),
+                Some(DeathCause::Pool) => String::from("POISONED BY A POOL"),
+                Some(DeathCause::Orb) => String::from("LOST IN A CRYSTAL ORB"),
+                Some(DeathCause::PitTrap) => String::from("FELL INTO A PIT"),
+                Some(DeathCause::DartTrap) => String::from("STRUCK BY A POISON DART"),
+                None => String::from("DIED"),
+            },
+        };
+
+        format!(
+            "{} ON LEVEL {} AFTER {} TURNS",
+            what, self.level, self.turns
+        )
+    }
+
+    /// Convert to a tab-separated line
+    fn to_line(&self) -> String {
+        let treasures: Vec<&str> = self.treasures.iter().map(|t| t.name()).collect();
+
+        let treasures = if treasures.is_empty() {
+            String::from("-")
+        } else {
+            treasures.join(",")
+        };
+
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.date,
+            self.seed,
+            self.difficulty.name(),
+            self.race.name(),
+            self.gender.name(),
+            self.outcome.name(),
+            death_cause_to_str(self.death_cause),
+            self.level,
+            self.turns,
+            self.score,
+            self.gp,
+            self.weapon.name(),
+            self.armor.name(),
+            treasures,
+            self.castle.as_deref().unwrap_or("-"),
+            self.generator.as_deref().unwrap_or("-")
+        )
+    }
+
+    /// Parse a tab-separated line
+    ///
+    /// Lines from before castle files were recorded have no castle field, and
+    /// lines from before generators were recorded have no generator field.
+    fn from_line(line: &str) -> Option<RunRecord> {
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        if !(14..=16).contains(&fields.len()) {
+            return None;
+        }
+
+        let optional = |i: usize| match fields.get(i) {
+            None | Some(&"-") => None,
+            Some(f) => Some(String::from(*f)),
+        };
+
+        let castle = optional(14);
+        let generator = optional(15);
+
+        let mut treasures = Vec::new();
+
+        if fields[13] != "-" {
+            for t in fields[13].split(',') {
+                treasures.push(TreasureType::from_name(t)?);
+            }
+        }
+
+        Some(RunRecord {
+            date: String::from(fields[0]),
+            seed: fields[1].parse().ok()?,
+            castle,
+            generator,
+            difficulty: Difficulty::from_name(fields[2])?,
+            race: Race::from_name(fields[3])?,
+            gender: Gender::from_name(fields[4])?,
+            outcome: Outcome::from_name(fields[5])?,
+            death_cause: death_cause_from_str(fields[6])?,
+            level: fields[7].parse().ok()?,
+            turns: fields[8].parse().ok()?,
+            score: fields[9].parse().ok()?,
+            gp: fields[10].parse().ok()?,
+            weapon: WeaponType::from_name(fields[11])?,
+            armor: ArmorType::from_name(fields[12])?,
+            treasures,
+        })
+    }
+}
+
+pub struct History {}
+
+impl History {
+    /// Return the default location of the history file
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|d| d.join("history.txt"))
+    }
+
+    /// Load all runs from a file, oldest first
+    ///
+    /// A missing file is an empty history. Lines that can't be parsed are
+    /// skipped.
+    pub fn load(path: &Path) -> io::Result<Vec<RunRecord>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(text.lines().filter_map(RunRecord::from_line).collect())
+    }
+
+    /// Add a run to the end of a file, creating it if needed
+    pub fn append(path: &Path, record: &RunRecord) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        writeln!(file, "{}", record.to_line())
+    }
+}
+
+/// Convert a death cause to a string, "-" for none
+fn death_cause_to_str(cause: Option<DeathCause>) -> String {
+    match cause {
+        None => String::from("-"),
+        Some(DeathCause::Monster(m)) => format!("MONSTER:{}", m.name()),
+        Some(DeathCause::ExplodingChest) => String::from("CHEST"),
+        Some(DeathCause::Fireball) => String::from("FIREBALL"),
+        Some(DeathCause::Deathspell) => String::from("DEATHSPELL"),
+        Some(DeathCause::Web) => String::from("WEB"),
+        Some(DeathCause::Pool) => String::from("POOL"),
+        Some(DeathCause::Orb) => String::from("ORB"),
+        Some(DeathCause::PitTrap) => String::from("PIT"),
+        Some(DeathCause::DartTrap) => String::from("DART"),
+    }
+}
+
+/// Parse a death cause string
+///
+/// The outer Option is None on a parse error, the inner is the cause.
+fn death_cause_from_str(s: &str) -> Option<Option<DeathCause>> {
+    let cause = match s {
+        "-" => return Some(None),
+        "CHEST" => DeathCause::ExplodingChest,
+        "FIREBALL" => DeathCause::Fireball,
+        "DEATHSPELL" => DeathCause::Deathspell,
+        "WEB" => DeathCause::Web,
+        "POOL" => DeathCause::Pool,
+        "ORB" => DeathCause::Orb,
+        "PIT" => DeathCause::PitTrap,
+        "DART" => DeathCause::DartTrap,
+        _ => {
+            if let Some(m) = s.strip_prefix("MONSTER:") {
+                DeathCause::Monster(MonsterType::from_name(m)?)
+            } else {
+                return None;
+            }
+        }
+    };
+
+    Some(Some(cause))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> RunRecord {
+        RunRecord {
+            date: String::from("2021-01-01"),
+            seed: 1234,
+            castle: None,
+            generator: Some(String::from("VAULTS")),
+            difficulty: Difficulty::Hard,
+            race: Race::Dwarf,
+            gender: Gender::Male,
+            outcome: Outcome::Died,
+            death_cause: Some(DeathCause::Monster(MonsterType::Balrog)),
+            level: 6,
+            turns: 412,
+            score: 0,
+            gp: 80,
+            weapon: WeaponType::Sword,
+            armor: ArmorType::Chainmail,
+            treasures: vec![TreasureType::RubyRed, TreasureType::Silmaril],
+        }
+    }
+
+    #[test]
+    fn lines_read_back_the_same() {
+        let r = record();
+        assert_eq!(RunRecord::from_line(&r.to_line()), Some(r));
+
+        let mut r = record();
+        r.castle = Some(String::from("castles/tutorial.txt"));
+        r.generator = None;
+        r.death_cause = None;
+        r.outcome = Outcome::Won;
+        r.treasures.clear();
+        assert_eq!(RunRecord::from_line(&r.to_line()), Some(r));
+    }
+
+    #[test]
+    fn reads_lines_from_before_castle_files() {
+        let mut r = record();
+        r.generator = None;
+
+        let line = r.to_line();
+        let old = line.strip_suffix("\t-\t-").unwrap();
+
+        assert_eq!(RunRecord::from_line(old), Some(r));
+    }
+
+    #[test]
+    fn reads_lines_from_before_generators() {
+        let mut r = record();
+        r.generator = None;
+
+        let line = r.to_line();
+        let old = line.strip_suffix("\t-").unwrap();
+
+        assert_eq!(RunRecord::from_line(old), Some(r));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let line = record().to_line();
+
+        assert_eq!(RunRecord::from_line(""), None);
+        assert_eq!(RunRecord::from_line(&line.replace("1234", "lots")), None);
+        assert_eq!(
+            RunRecord::from_line(&line.replace("BALROG", "TROLLOP")),
+            None
+        );
+        assert_eq!(RunRecord::from_line(&format!("{}\textra", line)), None);
+    }
+
+    #[test]
+    fn epitaphs() {
+        assert_eq!(
+            record().epitaph(),
+            "SLAIN BY A BALROG ON LEVEL 6 AFTER 412 TURNS"
+        );
+
+        let mut r = record();
+        r.outcome = Outcome::Won;
+        assert_eq!(r.epitaph(), "ESCAPED WITH THE ORB OF ZOT AFTER 412 TURNS");
+    }
+
+    #[test]
+    fn from_game_records_the_castle_file() {
+        let dungeon = crate::castle::parse("SIZE 3 1 1\nLEVEL 1\nE Z R:OGRE\n", Difficulty::Easy);
+
+        let mut game = Game::from_dungeon(dungeon.unwrap(), Difficulty::Easy, 99);
+        game.set_castle_file("tiny\tcastle.txt");
+        game.quit();
+
+        let r = RunRecord::from_game(&game).unwrap();
+        assert_eq!(r.seed, 99);
+        assert_eq!(r.castle.as_deref(), Some("tiny castle.txt"));
+        assert_eq!(r.generator, None);
+        assert_eq!(RunRecord::from_line(&r.to_line()), Some(r));
+    }
+
+    #[test]
+    fn from_game_records_the_generator() {
+        let generator = crate::generator::VaultGenerator;
+
+        let mut game = Game::with_generator(&generator, 8, 8, 8, Difficulty::Hard, 7);
+        game.quit();
+
+        let r = RunRecord::from_game(&game).unwrap();
+        assert_eq!(r.seed, 7);
+        assert_eq!(r.castle, None);
+        assert_eq!(r.generator.as_deref(), Some("VAULTS"));
+        assert_eq!(RunRecord::from_line(&r.to_line()), Some(r));
+    }
+
+    #[test]
+    fn from_game_needs_a_finished_game() {
+        let game = Game::with_seed(8, 8, 8, Difficulty::Classic, 1);
+
+        assert_eq!(RunRecord::from_game(&game), None);
+    }
+}
@@ -1,10 +1,20 @@
 pub mod armor;
+pub mod castle;
 pub mod curse;
+pub mod difficulty;
 pub mod dungeon;
 pub mod error;
+pub mod export;
 pub mod game;
+pub mod generator;
+pub mod highscore;
+pub mod history;
 pub mod monster;
 pub mod player;
+pub mod reputation;
+pub mod rng;
 pub mod room;
+pub mod stats;
 pub mod treasure;
+pub mod vendor;
 pub mod weapon;
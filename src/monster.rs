@@ -1,6 +1,10 @@
+use crate::difficulty::Difficulty;
+use crate::treasure::TreasureType;
+use crate::vendor::Vendor;
+
 pub const MONSTER_COUNT: u32 = 13;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Hash, Eq)]
 pub enum MonsterType {
     Kobold,
     Orc,
@@ -17,35 +21,237 @@ pub enum MonsterType {
     Vendor,
 }
 
+impl MonsterType {
+    /// Return the monster's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            MonsterType::Kobold => "KOBOLD",
+            MonsterType::Orc => "ORC",
+            MonsterType::Wolf => "WOLF",
+            MonsterType::Goblin => "GOBLIN",
+            MonsterType::Ogre => "OGRE",
+            MonsterType::Troll => "TROLL",
+            MonsterType::Bear => "BEAR",
+            MonsterType::Minotaur => "MINOTAUR",
+            MonsterType::Gargoyle => "GARGOYLE",
+            MonsterType::Chimera => "CHIMERA",
+            MonsterType::Balrog => "BALROG",
+            MonsterType::Dragon => "DRAGON",
+            MonsterType::Vendor => "VENDOR",
+        }
+    }
+
+    /// Return the monster with a name
+    pub fn from_name(name: &str) -> Option<MonsterType> {
+        match name {
+            "KOBOLD" => Some(MonsterType::Kobold),
+            "ORC" => Some(MonsterType::Orc),
+            "WOLF" => Some(MonsterType::Wolf),
+            "GOBLIN" => Some(MonsterType::Goblin),
+            "OGRE" => Some(MonsterType::Ogre),
+            "TROLL" => Some(MonsterType::Troll),
+            "BEAR" => Some(MonsterType::Bear),
+            "MINOTAUR" => Some(MonsterType::Minotaur),
+            "GARGOYLE" => Some(MonsterType::Gargoyle),
+            "CHIMERA" => Some(MonsterType::Chimera),
+            "BALROG" => Some(MonsterType::Balrog),
+            "DRAGON" => Some(MonsterType::Dragon),
+            "VENDOR" => Some(MonsterType::Vendor),
+            _ => None,
+        }
+    }
+
+    /// Return "A" or "AN", whichever goes before the monster's name
+    pub fn article(&self) -> &'static str {
+        match self.name().chars().next() {
+            Some('A') | Some('E') | Some('I') | Some('O') | Some('U') => "AN",
+            _ => "A",
+        }
+    }
+}
+
+/// Something special a monster can do in a fight
+///
+/// The original game's monsters only differed in how tough they were.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Ability {
+    Breath,         // Some attacks are fire breath, which armor doesn't stop
+    Regenerate,     // Heals a little every round
+    DoubleAttack,   // Attacks twice a round
+    MeleeResistant, // Only takes half damage from weapons
+    Poison,         // Hits can poison the player
+    Fire,           // Hits can burn up the player's flares
+    StealGold,      // Hits can grab some of the player's gold
+    Charge,         // Can knock the player into the next room
+}
+
+/// What a monster might be carrying when it's defeated
+///
+/// Gold is a range of GP, and everything else is a chance out of 20.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LootTable {
+    pub gold: (u32, u32),
+    pub flares: u32,
+    pub potion: u32,
+    pub armor_repair: u32,
+    pub whetstone: u32,
+    pub key: u32,
+    pub treasure: u32,
+}
+
+impl LootTable {
+    /// The original game's loot, which was the same for every monster
+    pub fn classic() -> LootTable {
+        LootTable {
+            gold: (1, 1000),
+            flares: 0,
+            potion: 0,
+            armor_repair: 0,
+            whetstone: 0,
+            key: 0,
+            treasure: 0,
+        }
+    }
+}
+
+/// What it takes to bribe a monster
+///
+/// A bribe worth the price always works, and a cheaper one is less likely to.
+/// The favorite treasure is worth twice as much to the monster.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BribeTastes {
+    pub price: u32,
+    pub takes_gold: bool,
+    pub favorite: Option<TreasureType>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Monster {
     monster_type: MonsterType,
     hp: u32,
+    max_hp: u32,
     damage: u32,
     break_weapon: bool,
     has_runestaff: bool,
+    has_key: bool,
     webbed: u32, // How many turns left stuck in a web
+    abilities: &'static [Ability],
+    gold: u32, // Stolen from the player
+    vendor: Option<Vendor>,
 }
 
 impl Monster {
-    pub fn new(monster_type: MonsterType, has_runestaff: bool) -> Monster {
+    pub fn new(monster_type: MonsterType, has_runestaff: bool, difficulty: Difficulty) -> Monster {
         let monster_num = Monster::get_monster_num(monster_type);
 
         let m1 = monster_num + 1; // Change to 1-based
 
-        let hp = m1 + 2;
-        let damage = 1 + m1 / 2;
+        let hp = difficulty.monster_hp(m1 + 2);
+        let damage = difficulty.monster_damage(1 + m1 / 2);
 
         let break_weapon =
             monster_type == MonsterType::Gargoyle || monster_type == MonsterType::Dragon;
 
+        let abilities = if difficulty.monster_abilities() {
+            Monster::get_abilities(monster_type)
+        } else {
+            &[]
+        };
+
         Monster {
             monster_type,
             hp,
+            max_hp: hp,
             damage,
             break_weapon,
             has_runestaff,
+            has_key: false,
             webbed: 0,
+            abilities,
+            gold: 0,
+            vendor: if monster_type == MonsterType::Vendor {
+                Some(Vendor::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Return the abilities for a given monster type
+    fn get_abilities(monster_type: MonsterType) -> &'static [Ability] {
+        match monster_type {
+            MonsterType::Wolf => &[Ability::DoubleAttack],
+            MonsterType::Goblin => &[Ability::StealGold],
+            MonsterType::Troll => &[Ability::Regenerate],
+            MonsterType::Minotaur => &[Ability::Charge],
+            MonsterType::Gargoyle => &[Ability::MeleeResistant],
+            MonsterType::Chimera => &[Ability::Poison],
+            MonsterType::Balrog => &[Ability::Fire],
+            MonsterType::Dragon => &[Ability::Breath],
+            _ => &[],
+        }
+    }
+
+    /// Return the loot table for a given monster type
+    ///
+    /// Tougher monsters carry more gold. Small fry carry flares and odds and
+    /// ends, and the biggest monsters sometimes have a treasure.
+    pub fn loot_table(monster_type: MonsterType) -> LootTable {
+        let m1 = Monster::get_monster_num(monster_type) + 1;
+
+        let (flares, potion, armor_repair, whetstone, key, treasure) = match monster_type {
+            MonsterType::Kobold => (6, 0, 1, 1, 1, 0),
+            MonsterType::Orc => (5, 1, 3, 2, 1, 0),
+            MonsterType::Wolf => (0, 0, 0, 0, 0, 0),
+            MonsterType::Goblin => (5, 1, 2, 2, 2, 0),
+            MonsterType::Ogre => (3, 3, 2, 3, 1, 0),
+            MonsterType::Troll => (2, 3, 2, 2, 1, 0),
+            MonsterType::Bear => (0, 0, 0, 0, 0, 0),
+            MonsterType::Minotaur => (2, 3, 3, 3, 1, 1),
+            MonsterType::Gargoyle => (0, 2, 0, 0, 1, 1),
+            MonsterType::Chimera => (0, 3, 0, 0, 1, 1),
+            MonsterType::Balrog => (0, 4, 1, 1, 2, 2),
+            MonsterType::Dragon => (0, 4, 0, 0, 2, 3),
+            MonsterType::Vendor => (0, 0, 0, 0, 0, 0),
+        };
+
+        LootTable {
+            gold: (m1 * 40, m1 * 160),
+            flares,
+            potion,
+            armor_repair,
+            whetstone,
+            key,
+            treasure,
+        }
+    }
+
+    /// Return what it takes to bribe a monster type
+    ///
+    /// In the original game every monster took any treasure the player had.
+    pub fn bribe_tastes(monster_type: MonsterType) -> BribeTastes {
+        let m1 = Monster::get_monster_num(monster_type) + 1;
+
+        let (takes_gold, favorite) = match monster_type {
+            MonsterType::Kobold => (true, None),
+            MonsterType::Orc => (true, Some(TreasureType::RubyRed)),
+            MonsterType::Wolf => (false, None),
+            MonsterType::Goblin => (true, Some(TreasureType::GreenGem)),
+            MonsterType::Ogre => (true, Some(TreasureType::PalePearl)),
+            MonsterType::Troll => (true, Some(TreasureType::OpalEye)),
+            MonsterType::Bear => (false, None),
+            MonsterType::Minotaur => (false, Some(TreasureType::NornStone)),
+            MonsterType::Gargoyle => (false, Some(TreasureType::Palantir)),
+            MonsterType::Chimera => (false, Some(TreasureType::BlueFlame)),
+            MonsterType::Balrog => (false, Some(TreasureType::BlueFlame)),
+            MonsterType::Dragon => (false, Some(TreasureType::Silmaril)),
+            MonsterType::Vendor => (true, None),
+        };
+
+        BribeTastes {
+            price: m1 * 750,
+            takes_gold,
+            favorite,
         }
     }
 
@@ -78,6 +284,60 @@ impl Monster {
         self.break_weapon
     }
 
+    /// Return the monster's abilities
+    pub fn abilities(&self) -> &[Ability] {
+        self.abilities
+    }
+
+    /// True if the monster has an ability
+    pub fn has_ability(&self, ability: Ability) -> bool {
+        self.abilities.contains(&ability)
+    }
+
+    /// Heal the monster, but not past its starting hitpoints
+    ///
+    /// Return how much it healed.
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let healed = std::cmp::min(amount, self.max_hp - self.hp);
+
+        self.hp += healed;
+
+        healed
+    }
+
+    /// True if the monster flees from a well-armed player
+    pub fn is_cowardly(&self) -> bool {
+        matches!(
+            self.monster_type,
+            MonsterType::Kobold | MonsterType::Goblin | MonsterType::Orc
+        )
+    }
+
+    /// True if the monster is down to a third of its hitpoints or less
+    pub fn is_wounded(&self) -> bool {
+        self.hp * 3 <= self.max_hp
+    }
+
+    /// Return the gold the monster has stolen
+    pub fn gold(&self) -> u32 {
+        self.gold
+    }
+
+    /// Give the monster some stolen gold
+    pub fn add_gold(&mut self, amount: u32) {
+        self.gold += amount;
+    }
+
+    /// Return the vendor's stock and cash, if this is a vendor
+    pub fn vendor(&self) -> Option<&Vendor> {
+        self.vendor.as_ref()
+    }
+
+    /// Return the vendor's stock and cash, if this is a vendor, to change it
+    pub fn vendor_mut(&mut self) -> Option<&mut Vendor> {
+        self.vendor.as_mut()
+    }
+
     /// Damage the monster
     ///
     /// Return true if defeated
@@ -101,6 +361,16 @@ impl Monster {
         self.has_runestaff
     }
 
+    /// True if the monster has a key to a locked vault
+    pub fn has_key(&self) -> bool {
+        self.has_key
+    }
+
+    /// Give the monster a key
+    pub fn give_key(&mut self) {
+        self.has_key = true;
+    }
+
     /// Return monster webbed status
     pub fn webbed(&self) -> &u32 {
         &self.webbed
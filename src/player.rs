@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use crate::armor::{Armor, ArmorType};
 use crate::curse::CurseType;
 use crate::error::Error;
+use crate::monster::MonsterType;
 use crate::treasure::TreasureType;
 use crate::weapon::{Weapon, WeaponType};
 
@@ -19,6 +20,25 @@ pub enum Gender {
     Female,
 }
 
+impl Gender {
+    /// Return the gender's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gender::Male => "MALE",
+            Gender::Female => "FEMALE",
+        }
+    }
+
+    /// Return the gender with a name
+    pub fn from_name(name: &str) -> Option<Gender> {
+        match name {
+            "MALE" => Some(Gender::Male),
+            "FEMALE" => Some(Gender::Female),
+            _ => None,
+        }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Race {
     Hobbit,
@@ -27,6 +47,30 @@ pub enum Race {
     Dwarf,
 }
 
+impl Race {
+    /// Return the race's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Race::Hobbit => "HOBBIT",
+            Race::Elf => "ELF",
+            Race::Human => "HUMAN",
+            Race::Dwarf => "DWARF",
+        }
+    }
+
+    /// Return the race with a name
+    pub fn from_name(name: &str) -> Option<Race> {
+        match name {
+            "HOBBIT" => Some(Race::Hobbit),
+            "ELF" => Some(Race::Elf),
+            "HUMAN" => Some(Race::Human),
+            "DWARF" => Some(Race::Dwarf),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Player {
     x: u32,
     y: u32,
@@ -52,9 +96,12 @@ pub struct Player {
     orb_of_zot: bool,
 
     flares: u32,
+    keys: u32,
 
     blind: bool,
     book_stuck: bool,
+    poisoned: u32,                    // Turns left
+    poisoned_by: Option<MonsterType>, // Whatever did it, for if the poison kills
 
     curses: Vec<CurseType>,
 }
@@ -83,6 +130,8 @@ impl Player {
 
             blind: false,
             book_stuck: false,
+            poisoned: 0,
+            poisoned_by: None,
 
             armor: Armor::new(ArmorType::None),
             weapon: Weapon::new(WeaponType::None),
@@ -93,6 +142,7 @@ impl Player {
             runestaff: false,
 
             flares: 0,
+            keys: 0,
 
             curses: Vec::new(),
         }
@@ -106,7 +156,7 @@ impl Player {
     }
 
     /// Set the race and all the corresponding points
-    pub fn init(&mut self, race: Race) {
+    pub fn init(&mut self, race: Race, gp: u32) {
         let race_id = Player::get_id_by_race(race);
 
         self.stat.insert(Stat::Strength, 2 + (race_id + 1) * 2);
@@ -121,9 +171,12 @@ impl Player {
 
         self.race = race;
 
-        self.gp = 60;
+        self.gp = gp;
 
         self.flares = 0;
+        self.keys = 0;
+        self.poisoned = 0;
+        self.poisoned_by = None;
 
         self.treasures.clear();
         self.curses.clear();
@@ -192,10 +245,8 @@ impl Player {
         *self.stat(&stat) == 18
     }
 
-    /// Give the player some armor
-    pub fn purchase_armor(&mut self, a: ArmorType, is_vendor: bool) -> Result<(), Error> {
-        let armor_cost = Armor::cost(a, is_vendor);
-
+    /// Give the player some armor for a price
+    pub fn purchase_armor(&mut self, a: ArmorType, armor_cost: u32) -> Result<(), Error> {
         if armor_cost > self.gp {
             return Err(Error::NotEnoughGP);
         }
@@ -207,10 +258,8 @@ impl Player {
         Ok(())
     }
 
-    /// Give the player a weapon
-    pub fn purchase_weapon(&mut self, w: WeaponType, is_vendor: bool) -> Result<(), Error> {
-        let weapon_cost = Weapon::cost(w, is_vendor);
-
+    /// Give the player a weapon for a price
+    pub fn purchase_weapon(&mut self, w: WeaponType, weapon_cost: u32) -> Result<(), Error> {
         if weapon_cost > self.gp {
             return Err(Error::NotEnoughGP);
         }
@@ -279,6 +328,11 @@ impl Player {
         self.flares
     }
 
+    /// Return key count
+    pub fn keys(&self) -> u32 {
+        self.keys
+    }
+
     /// Return number of gold pieces
     pub fn gp(&self) -> &u32 {
         &self.gp
@@ -291,6 +345,11 @@ impl Player {
         self.gp = new_gp as u32;
     }
 
+    /// Set player's GP
+    pub fn set_gp(&mut self, gp: u32) {
+        self.gp = gp;
+    }
+
     /// Return player's weapon
     pub fn weapon(&self) -> &Weapon {
         &self.weapon
@@ -356,6 +415,34 @@ impl Player {
         armor_destroyed
     }
 
+    /// Repair armor
+    ///
+    /// Return how much was repaired
+    pub fn repair_armor(&mut self, amount: u32) -> u32 {
+        self.armor_mut().repair(amount)
+    }
+
+    /// Wear down the weapon
+    ///
+    /// Return true if the weapon is worn out, which leaves the player with
+    /// nothing
+    pub fn wear_weapon(&mut self, amount: u32) -> bool {
+        let worn_out = self.weapon.wear(amount) && self.weapon.weapon_type() != WeaponType::None;
+
+        if worn_out {
+            self.weapon = Weapon::new(WeaponType::None);
+        }
+
+        worn_out
+    }
+
+    /// Repair weapon
+    ///
+    /// Return how much was repaired
+    pub fn repair_weapon(&mut self, amount: u32) -> u32 {
+        self.weapon.repair(amount)
+    }
+
     /// Returns true if the player is dead
     pub fn is_dead(&self) -> bool {
         *self.stat(&Stat::Strength) == 0
@@ -496,6 +583,13 @@ impl Player {
         self.flares
     }
 
+    /// change key count
+    pub fn change_keys(&mut self, delta: i32) -> u32 {
+        self.keys = std::cmp::max(0, self.keys as i32 + delta) as u32;
+
+        self.keys
+    }
+
     /// True if a book is stuck to the player's hands
     pub fn book_stuck(&self) -> &bool {
         &self.book_stuck
@@ -506,6 +600,31 @@ impl Player {
         self.book_stuck = stuck;
     }
 
+    /// Return how many more turns the player is poisoned for
+    pub fn poisoned(&self) -> u32 {
+        self.poisoned
+    }
+
+    /// Return the monster that poisoned the player, if they're poisoned
+    pub fn poisoned_by(&self) -> Option<MonsterType> {
+        self.poisoned_by
+    }
+
+    /// Poison the player for some turns
+    pub fn set_poisoned(&mut self, turns: u32, by: MonsterType) {
+        self.poisoned = turns;
+        self.poisoned_by = Some(by);
+    }
+
+    /// Take a turn off the poison
+    pub fn poison_wear_off(&mut self) {
+        self.poisoned = self.poisoned.saturating_sub(1);
+
+        if self.poisoned == 0 {
+            self.poisoned_by = None;
+        }
+    }
+
     /// Curse the player
     pub fn add_curse(&mut self, curse: CurseType) {
         if curse != CurseType::None && !self.curses.contains(&curse) {
@@ -513,6 +632,16 @@ impl Player {
         }
     }
 
+    /// Remove a curse from the player
+    pub fn remove_curse(&mut self, curse: CurseType) {
+        self.curses.retain(|c| *c != curse);
+    }
+
+    /// Return the curses on the player, in the order they were caught
+    pub fn curses(&self) -> &[CurseType] {
+        &self.curses
+    }
+
     /// True if the player has a curse
     pub fn has_curse(&self, curse: CurseType) -> bool {
         self.curses.contains(&curse)
@@ -0,0 +1,216 @@
+use crate::dungeon::Dungeon;
+
+/// Vendors who think this little of the player attack on sight
+pub const HOSTILE_REPUTATION: i32 = -25;
+
+/// How far the player's reputation can go either way
+pub const MAX_REPUTATION: i32 = 100;
+
+/// How many turns it takes word of something the player did to travel one room
+pub const GOSSIP_TURNS: u32 = 5;
+
+/// Something the player did that vendors will hear about
+#[derive(Debug, Clone, Copy)]
+struct Deed {
+    turn: u32,
+    location: (u32, u32, u32),
+    change: i32,
+}
+
+/// What vendors think of the player
+///
+/// Word of what the player does starts where they did it and spreads a room
+/// every few turns, so a vendor's opinion depends on what's reached them so
+/// far, plus whatever they saw for themselves. In the original game attacking
+/// one vendor made them all angry at once, and bribing any vendor calmed them
+/// all down.
+#[derive(Debug, Clone)]
+pub struct Reputation {
+    deeds: Vec<Deed>,
+    xsize: u32,
+    ysize: u32,
+}
+
+impl Reputation {
+    /// Make a reputation for a castle with levels of this size
+    pub fn new(xsize: u32, ysize: u32) -> Reputation {
+        Reputation {
+            deeds: Vec::new(),
+            xsize,
+            ysize,
+        }
+    }
+
+    /// Remember something the player did
+    ///
+    /// Return a number for the deed, for anyone who saw it to remember.
+    pub fn record(&mut self, turn: u32, location: (u32, u32, u32), change: i32) -> usize {
+        self.deeds.push(Deed {
+            turn,
+            location,
+            change,
+        });
+
+        self.deeds.len() - 1
+    }
+
+    /// Return the player's reputation once everyone has heard everything
+    pub fn overall(&self) -> i32 {
+        Reputation::total(self.deeds.iter())
+    }
+
+    /// Return the player's reputation as far as a vendor at a location has
+    /// heard by a given turn
+    ///
+    /// Deeds the vendor witnessed count no matter where they are now.
+    pub fn heard_at(&self, location: (u32, u32, u32), turn: u32, witnessed: &[usize]) -> i32 {
+        Reputation::total(self.deeds.iter().enumerate().filter_map(|(i, d)| {
+            let heard = d.turn + self.distance(d.location, location) * GOSSIP_TURNS <= turn;
+
+            if heard || witnessed.contains(&i) {
+                Some(d)
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Add up deeds, keeping within the limits
+    fn total<'a>(deeds: impl Iterator<Item = &'a Deed>) -> i32 {
+        deeds.fold(0, |rep, d| {
+            (rep + d.change).clamp(-MAX_REPUTATION, MAX_REPUTATION)
+        })
+    }
+
+    /// Return how many rooms word has to travel between two places
+    ///
+    /// Levels wrap around, so word can go either way across one. Going
+    /// between levels counts as a couple of rooms.
+    fn distance(&self, a: (u32, u32, u32), b: (u32, u32, u32)) -> u32 {
+        Dungeon::distance(self.xsize, self.ysize, (a.0, a.1), (b.0, b.1)) + a.2.abs_diff(b.2) * 2
+    }
+
+    /// True if a vendor with this opinion of the player attacks on sight
+    pub fn is_hostile(reputation: i32) -> bool {
+        reputation <= HOSTILE_REPUTATION
+    }
+
+    /// True if a vendor with this opinion of the player will sell to them
+    ///
+    /// Vendors who don't like the player will still buy treasures.
+    pub fn will_sell(reputation: i32) -> bool {
+        reputation >= 0
+    }
+
+    /// Adjust a vendor's asking price by their opinion of the player
+    ///
+    /// The best reputation knocks a quarter off, and the worst adds a quarter.
+    pub fn price(reputation: i32, cost: u32) -> u32 {
+        (cost as i64 * (400 - reputation as i64) / 400) as u32
+    }
+
+    /// Adjust what a vendor offers for a treasure by their opinion of the
+    /// player
+    pub fn offer(reputation: i32, value: u32) -> u32 {
+        (value as i64 * (400 + reputation as i64) / 400) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_spreads_a_room_at_a_time() {
+        let mut rep = Reputation::new(8, 8);
+
+        rep.record(10, (0, 0, 0), -30);
+
+        // Three rooms away
+        let there = (2, 1, 0);
+
+        assert_eq!(rep.heard_at(there, 10 + 3 * GOSSIP_TURNS - 1, &[]), 0);
+        assert_eq!(rep.heard_at(there, 10 + 3 * GOSSIP_TURNS, &[]), -30);
+
+        // Right where it happened
+        assert_eq!(rep.heard_at((0, 0, 0), 10, &[]), -30);
+    }
+
+    #[test]
+    fn word_spreads_around_the_edges() {
+        let mut rep = Reputation::new(8, 8);
+
+        rep.record(0, (0, 0, 0), -30);
+
+        // One room west, across the edge of the level
+        assert_eq!(rep.heard_at((7, 0, 0), GOSSIP_TURNS - 1, &[]), 0);
+        assert_eq!(rep.heard_at((7, 0, 0), GOSSIP_TURNS, &[]), -30);
+
+        // And one room north of that
+        assert_eq!(rep.heard_at((7, 7, 0), 2 * GOSSIP_TURNS, &[]), -30);
+    }
+
+    #[test]
+    fn levels_are_farther_apart() {
+        let mut rep = Reputation::new(8, 8);
+
+        rep.record(0, (0, 0, 0), 10);
+
+        assert_eq!(rep.heard_at((0, 0, 1), GOSSIP_TURNS, &[]), 0);
+        assert_eq!(rep.heard_at((0, 0, 1), 2 * GOSSIP_TURNS, &[]), 10);
+    }
+
+    #[test]
+    fn witnesses_know_right_away() {
+        let mut rep = Reputation::new(8, 8);
+
+        let deed = rep.record(0, (0, 0, 0), -30);
+        rep.record(0, (0, 0, 0), 5);
+
+        let far = (7, 7, 7);
+
+        assert_eq!(rep.heard_at(far, 0, &[]), 0);
+        assert_eq!(rep.heard_at(far, 0, &[deed]), -30);
+        assert_eq!(rep.overall(), -25);
+    }
+
+    #[test]
+    fn total_stays_within_the_limits() {
+        let mut rep = Reputation::new(8, 8);
+
+        for _ in 0..10 {
+            rep.record(0, (0, 0, 0), 30);
+        }
+
+        assert_eq!(rep.overall(), MAX_REPUTATION);
+
+        // Bad deeds count from the limit, not from the sum of everything
+        rep.record(0, (0, 0, 0), -30);
+        assert_eq!(rep.overall(), MAX_REPUTATION - 30);
+
+        for _ in 0..10 {
+            rep.record(0, (0, 0, 0), -30);
+        }
+
+        assert_eq!(rep.overall(), -MAX_REPUTATION);
+    }
+
+    #[test]
+    fn hostile_and_will_sell() {
+        assert!(Reputation::will_sell(0));
+        assert!(!Reputation::will_sell(-1));
+        assert!(!Reputation::is_hostile(HOSTILE_REPUTATION + 1));
+        assert!(Reputation::is_hostile(HOSTILE_REPUTATION));
+    }
+
+    #[test]
+    fn price_and_offer() {
+        assert_eq!(Reputation::price(0, 1000), 1000);
+        assert_eq!(Reputation::price(MAX_REPUTATION, 1000), 750);
+        assert_eq!(Reputation::price(-MAX_REPUTATION, 1000), 1250);
+
+        assert_eq!(Reputation::offer(0, 1000), 1000);
+        assert_eq!(Reputation::offer(MAX_REPUTATION, 1000), 1250);
+        assert_eq!(Reputation::offer(-MAX_REPUTATION, 1000), 750);
+    }
+}
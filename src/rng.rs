@@ -0,0 +1,97 @@
+extern crate rand;
+
+use self::rand::rngs::StdRng;
+use self::rand::{Error, Rng, RngCore, SeedableRng};
+
+/// The game's random number generator
+///
+/// All of a game's randomness goes through its own generator so that a castle
+/// and its dice rolls can be reproduced from a seed.
+#[derive(Debug, Clone)]
+pub struct GameRng {
+    rng: StdRng,
+}
+
+impl GameRng {
+    /// Make a generator from a seed
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Roll a die (1d6, 2d7, etc.)
+    pub fn d(&mut self, count: u32, sides: u32) -> u32 {
+        let mut total = 0;
+
+        for _ in 0..count {
+            total += self.rng.gen_range(0..sides) + 1;
+        }
+
+        total
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Choose a new random seed
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.d(3, 7), b.d(3, 7));
+        }
+    }
+
+    #[test]
+    fn d_stays_in_range() {
+        let mut rng = GameRng::new(1);
+
+        for _ in 0..1000 {
+            let roll = rng.d(2, 6);
+            assert!((2..=12).contains(&roll));
+        }
+    }
+
+    #[test]
+    fn games_dont_share_a_generator() {
+        let mut a = GameRng::new(7);
+        let mut b = GameRng::new(7);
+
+        // Rolling on one doesn't move the other along
+        a.d(10, 20);
+
+        let expected: Vec<u32> = (0..10).map(|_| b.d(1, 20)).collect();
+
+        let mut c = GameRng::new(7);
+        let actual: Vec<u32> = (0..10).map(|_| c.d(1, 20)).collect();
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -18,34 +18,265 @@ pub enum RoomType {
     Book,
     Monster(Monster),
     Treasure(Treasure),
+    Vault(Box<RoomType>), // Locked, with a treasure or the orb of zot warp inside
+    PitTrap,
+    DartTrap,
+    AlarmTrap,
+    SlimeTrap,
 }
 
-#[derive(Debug)]
+/// What the player remembers about a room
+///
+/// This is what the room looked like the last time the player saw it, which
+/// might not be what's there now.
+#[derive(Debug, Clone)]
+pub struct Memory {
+    roomtype: RoomType,
+    turn: u32,
+}
+
+impl Memory {
+    /// Get the room type the player remembers
+    pub fn room_type(&self) -> &RoomType {
+        &self.roomtype
+    }
+
+    /// Get the turn the room was last seen on
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    /// Get how many turns ago the room was last seen
+    pub fn age(&self, now: u32) -> u32 {
+        now.saturating_sub(self.turn)
+    }
+}
+
+/// What a room with nothing (visible) in it looks like
+static EMPTY: RoomType = RoomType::Empty;
+
+/// How much a room type stands out, lowest first
+///
+/// Rooms keep their contents in this order, so the first thing in a room is
+/// the one that shows on the map.
+fn significance(roomtype: &RoomType) -> u32 {
+    match roomtype {
+        RoomType::Entrance => 0,
+        RoomType::Vault(_) => 1,
+        RoomType::Monster(_) => 2,
+        RoomType::Treasure(_) => 3,
+        RoomType::StairsDown => 4,
+        RoomType::StairsUp => 5,
+        RoomType::Warp(_) => 6,
+        RoomType::Sinkhole => 7,
+        RoomType::PitTrap | RoomType::DartTrap | RoomType::AlarmTrap | RoomType::SlimeTrap => 8,
+        RoomType::CrystalOrb => 9,
+        RoomType::Pool => 10,
+        RoomType::Book => 11,
+        RoomType::Chest => 12,
+        RoomType::Gold => 13,
+        RoomType::Flares => 14,
+        RoomType::Empty => 15,
+    }
+}
+
+/// True if the room type is a trap
+fn is_trap_type(roomtype: &RoomType) -> bool {
+    matches!(
+        roomtype,
+        RoomType::PitTrap | RoomType::DartTrap | RoomType::AlarmTrap | RoomType::SlimeTrap
+    )
+}
+
+#[derive(Debug, Clone)]
 pub struct Room {
-    pub roomtype: RoomType,
+    pub contents: Vec<RoomType>, // Most significant first, never Empty
     pub discovered: bool,
     pub curse: CurseType,
+    pub trap_found: bool,
+    memory: Option<Memory>,
 }
 
 impl Default for Room {
     fn default() -> Room {
         Room {
-            roomtype: RoomType::Empty,
+            contents: Vec::new(),
             discovered: false,
             curse: CurseType::None,
+            trap_found: false,
+            memory: None,
         }
     }
 }
 
 impl Room {
+    /// Make a room holding one thing
+    pub fn new(roomtype: RoomType) -> Room {
+        let mut room = Room::default();
+
+        room.add(roomtype);
+
+        room
+    }
+
     /// Mark the room as empty
     pub fn make_empty(&mut self) {
-        self.roomtype = RoomType::Empty;
+        self.contents.clear();
     }
 
-    /// Get the room type
+    /// Get the most significant thing in the room
     pub fn room_type(&self) -> &RoomType {
-        &self.roomtype
+        self.contents.first().unwrap_or(&EMPTY)
+    }
+
+    /// Get everything in the room, most significant first
+    pub fn contents(&self) -> &[RoomType] {
+        &self.contents
+    }
+
+    /// Get everything in the room the player can see, most significant first
+    ///
+    /// Traps can't be seen until they're found.
+    pub fn seen_contents(&self) -> Vec<&RoomType> {
+        self.contents
+            .iter()
+            .filter(|r| self.trap_found || !is_trap_type(r))
+            .collect()
+    }
+
+    /// Get the most significant thing in the room the player can see
+    pub fn seen_type(&self) -> &RoomType {
+        self.seen_contents().first().copied().unwrap_or(&EMPTY)
+    }
+
+    /// True if the room has this in it
+    pub fn has(&self, roomtype: &RoomType) -> bool {
+        self.contents.contains(roomtype)
+    }
+
+    /// Put something in the room
+    ///
+    /// It goes after anything else at least as significant. Adding `Empty`
+    /// does nothing.
+    pub fn add(&mut self, roomtype: RoomType) {
+        if roomtype == RoomType::Empty {
+            return;
+        }
+
+        let rank = significance(&roomtype);
+
+        let i = self
+            .contents
+            .iter()
+            .position(|r| significance(r) > rank)
+            .unwrap_or(self.contents.len());
+
+        self.contents.insert(i, roomtype);
+    }
+
+    /// Take one of something out of the room
+    ///
+    /// Returns false if it wasn't there.
+    pub fn remove(&mut self, roomtype: &RoomType) -> bool {
+        match self.contents.iter().position(|r| r == roomtype) {
+            Some(i) => {
+                self.contents.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get all the monsters in the room
+    pub fn monsters(&self) -> impl Iterator<Item = &Monster> {
+        self.contents.iter().filter_map(|r| match r {
+            RoomType::Monster(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// Get the first monster in the room
+    pub fn monster(&self) -> Option<&Monster> {
+        self.monsters().next()
+    }
+
+    /// Get the first monster in the room, to change it
+    pub fn monster_mut(&mut self) -> Option<&mut Monster> {
+        self.contents.iter_mut().find_map(|r| match r {
+            RoomType::Monster(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// Get the monsters in the room, to change them
+    pub fn monsters_mut(&mut self) -> impl Iterator<Item = &mut Monster> {
+        self.contents.iter_mut().filter_map(|r| match r {
+            RoomType::Monster(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// Take the first monster that's `wanted` out of the room
+    pub fn take_monster(&mut self, wanted: impl Fn(&Monster) -> bool) -> Option<Monster> {
+        let i = self
+            .contents
+            .iter()
+            .position(|r| matches!(r, RoomType::Monster(m) if wanted(m)))?;
+
+        match self.contents.remove(i) {
+            RoomType::Monster(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Take the room's `n`th monster out of the room, counting from 0
+    pub fn take_nth_monster(&mut self, n: usize) -> Option<Monster> {
+        let (i, _) = self
+            .contents
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| matches!(r, RoomType::Monster(_)))
+            .nth(n)?;
+
+        match self.contents.remove(i) {
+            RoomType::Monster(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Get the trap in the room, if there is one
+    pub fn trap(&self) -> Option<&RoomType> {
+        self.contents.iter().find(|r| is_trap_type(r))
+    }
+
+    /// True if the room has a trap in it
+    pub fn is_trap(&self) -> bool {
+        self.trap().is_some()
+    }
+
+    /// True if the room has a locked vault in it
+    pub fn is_locked(&self) -> bool {
+        self.contents
+            .iter()
+            .any(|r| matches!(r, RoomType::Vault(_)))
+    }
+
+    /// Lock the most significant thing in the room in a vault
+    pub fn lock(&mut self) {
+        if let Some(inside) = self.contents.first_mut() {
+            let vault = RoomType::Vault(Box::new(inside.clone()));
+            *inside = vault;
+        }
+    }
+
+    /// Open any vault in the room, leaving what was inside
+    pub fn unlock(&mut self) {
+        for r in std::mem::take(&mut self.contents) {
+            match r {
+                RoomType::Vault(inside) => self.add(*inside),
+                r => self.add(r),
+            }
+        }
     }
 
     /// Mark room as discovered
@@ -53,6 +284,29 @@ impl Room {
         self.discovered = discovered;
     }
 
+    /// Remember what the room looks like now
+    ///
+    /// This also marks it as discovered.
+    pub fn remember(&mut self, turn: u32) {
+        self.discovered = true;
+
+        self.memory = Some(Memory {
+            roomtype: self.seen_type().clone(),
+            turn,
+        });
+    }
+
+    /// Forget everything about the room
+    pub fn forget(&mut self) {
+        self.discovered = false;
+        self.memory = None;
+    }
+
+    /// Get what the player remembers about the room
+    pub fn memory(&self) -> Option<&Memory> {
+        self.memory.as_ref()
+    }
+
     /// Sets curse in a room
     pub fn set_curse(&mut self, curse_type: CurseType) {
         self.curse = curse_type;
@@ -63,3 +317,49 @@ impl Room {
         &self.curse
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remembers_what_was_there() {
+        let mut room = Room::new(RoomType::Gold);
+
+        assert!(room.memory().is_none());
+
+        room.remember(10);
+        room.make_empty();
+
+        let memory = room.memory().unwrap();
+        assert_eq!(*memory.room_type(), RoomType::Gold);
+        assert_eq!(*room.room_type(), RoomType::Empty);
+        assert!(room.discovered);
+    }
+
+    #[test]
+    fn memory_ages() {
+        let mut room = Room::new(RoomType::Pool);
+
+        room.remember(10);
+
+        assert_eq!(room.memory().unwrap().turn(), 10);
+        assert_eq!(room.memory().unwrap().age(10), 0);
+        assert_eq!(room.memory().unwrap().age(73), 63);
+
+        // Seeing it again starts over
+        room.remember(70);
+        assert_eq!(room.memory().unwrap().age(73), 3);
+    }
+
+    #[test]
+    fn forget_clears_the_memory() {
+        let mut room = Room::new(RoomType::Book);
+
+        room.remember(1);
+        room.forget();
+
+        assert!(room.memory().is_none());
+        assert!(!room.discovered);
+    }
+}
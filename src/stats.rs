@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+
+use crate::game::DeathCause;
+use crate::monster::MonsterType;
+
+/// Running statistics for a single game
+#[derive(Debug, Clone, Default)]
+pub struct GameStats {
+    pub rooms_discovered: u32,
+    pub monsters_killed: HashMap<MonsterType, u32>,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    pub spells_cast: u32,
+    pub gold_found: u32,
+    pub gold_spent: u32,
+    pub gold_from_vendors: u32,
+    pub treasures_collected: u32,
+    pub treasures_bribed: u32,
+    pub pools_drunk: u32,
+    pub books_opened: u32,
+    pub chests_opened: u32,
+    pub sinkholes: u32,
+    pub warps: u32,
+    pub death_cause: Option<DeathCause>,
+}
+
+impl GameStats {
+    pub fn new() -> GameStats {
+        Default::default()
+    }
+
+    /// Record a kill
+    pub fn add_kill(&mut self, monster_type: MonsterType) {
+        *self.monsters_killed.entry(monster_type).or_insert(0) += 1;
+    }
+
+    /// Return the number of monsters of a given type killed
+    pub fn kills(&self, monster_type: MonsterType) -> u32 {
+        *self.monsters_killed.get(&monster_type).unwrap_or(&0)
+    }
+
+    /// Return the total number of monsters killed
+    pub fn total_kills(&self) -> u32 {
+        self.monsters_killed.values().sum()
+    }
+}
@@ -12,6 +12,37 @@ pub enum TreasureType {
     Silmaril,
 }
 
+impl TreasureType {
+    /// Return the treasure's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            TreasureType::RubyRed => "RUBY RED",
+            TreasureType::NornStone => "NORN STONE",
+            TreasureType::PalePearl => "PALE PEARL",
+            TreasureType::OpalEye => "OPAL EYE",
+            TreasureType::GreenGem => "GREEN GEM",
+            TreasureType::BlueFlame => "BLUE FLAME",
+            TreasureType::Palantir => "PALANTIR",
+            TreasureType::Silmaril => "SILMARIL",
+        }
+    }
+
+    /// Return the treasure with a name
+    pub fn from_name(name: &str) -> Option<TreasureType> {
+        match name {
+            "RUBY RED" => Some(TreasureType::RubyRed),
+            "NORN STONE" => Some(TreasureType::NornStone),
+            "PALE PEARL" => Some(TreasureType::PalePearl),
+            "OPAL EYE" => Some(TreasureType::OpalEye),
+            "GREEN GEM" => Some(TreasureType::GreenGem),
+            "BLUE FLAME" => Some(TreasureType::BlueFlame),
+            "PALANTIR" => Some(TreasureType::Palantir),
+            "SILMARIL" => Some(TreasureType::Silmaril),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Treasure {
     pub treasure_type: TreasureType,
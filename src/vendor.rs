@@ -0,0 +1,281 @@
+use crate::armor::ArmorType;
+use crate::player::Stat;
+use crate::treasure::TreasureType;
+use crate::weapon::WeaponType;
+
+use std::collections::HashMap;
+
+/// How many turns it takes a vendor to get one more of everything in
+pub const RESTOCK_TURNS: u32 = 50;
+
+/// How much gold a vendor has on hand to buy treasures with
+pub const VENDOR_CASH: u32 = 10000;
+
+/// Something a vendor sells
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Goods {
+    Armor(ArmorType),
+    Weapon(WeaponType),
+    Potion(Stat),
+    Lamp,
+}
+
+/// What a vendor has to sell, and what they've paid for
+///
+/// In the original game every vendor had as much of everything as the player
+/// could buy, and came up with new treasure prices every time they were
+/// asked.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Vendor {
+    stock: HashMap<Goods, u32>,
+    cash: u32,
+    treasure_prices: HashMap<TreasureType, u32>, // What they first thought each treasure was worth
+    treasures_bought: u32,
+    restocked_turn: u32,
+    witnessed: Vec<usize>, // Things the player did right in front of them
+}
+
+impl Vendor {
+    pub fn new() -> Vendor {
+        let stock = Vendor::all_goods()
+            .iter()
+            .map(|g| (*g, Vendor::full_stock(*g)))
+            .collect();
+
+        Vendor {
+            stock,
+            cash: VENDOR_CASH,
+            treasure_prices: HashMap::new(),
+            treasures_bought: 0,
+            restocked_turn: 0,
+            witnessed: Vec::new(),
+        }
+    }
+
+    /// Return everything vendors sell
+    fn all_goods() -> [Goods; 10] {
+        [
+            Goods::Armor(ArmorType::Leather),
+            Goods::Armor(ArmorType::Chainmail),
+            Goods::Armor(ArmorType::Plate),
+            Goods::Weapon(WeaponType::Dagger),
+            Goods::Weapon(WeaponType::Mace),
+            Goods::Weapon(WeaponType::Sword),
+            Goods::Potion(Stat::Strength),
+            Goods::Potion(Stat::Intelligence),
+            Goods::Potion(Stat::Dexterity),
+            Goods::Lamp,
+        ]
+    }
+
+    /// Return how many of something a fully stocked vendor has
+    fn full_stock(goods: Goods) -> u32 {
+        match goods {
+            Goods::Armor(ArmorType::Plate) => 1,
+            Goods::Weapon(WeaponType::Sword) => 1,
+            Goods::Armor(_) => 2,
+            Goods::Weapon(_) => 2,
+            Goods::Potion(_) => 2,
+            Goods::Lamp => 1,
+        }
+    }
+
+    /// Return how many of something the vendor has
+    pub fn stock(&self, goods: Goods) -> u32 {
+        *self.stock.get(&goods).unwrap_or(&0)
+    }
+
+    /// Sell one of something
+    ///
+    /// Return false if the vendor is out of it.
+    pub fn sell(&mut self, goods: Goods, price: u32) -> bool {
+        match self.stock.get_mut(&goods) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                self.cash += price;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get more stock in, and top up the cash, for the turns gone by
+    pub fn restock(&mut self, turn: u32) {
+        let restocks = turn.saturating_sub(self.restocked_turn) / RESTOCK_TURNS;
+
+        if restocks == 0 {
+            return;
+        }
+
+        for (goods, count) in self.stock.iter_mut() {
+            *count = std::cmp::min(*count + restocks, Vendor::full_stock(*goods));
+        }
+
+        if self.cash < VENDOR_CASH {
+            self.cash = std::cmp::min(self.cash + restocks * 1000, VENDOR_CASH);
+        }
+
+        self.restocked_turn += restocks * RESTOCK_TURNS;
+    }
+
+    /// Return the gold the vendor has on hand
+    pub fn cash(&self) -> u32 {
+        self.cash
+    }
+
+    /// Return what the vendor first thought a treasure was worth, if they've
+    /// been asked before
+    pub fn treasure_price(&self, treasure_type: TreasureType) -> Option<u32> {
+        self.treasure_prices.get(&treasure_type).copied()
+    }
+
+    /// Remember what the vendor thinks a treasure is worth
+    pub fn set_treasure_price(&mut self, treasure_type: TreasureType, price: u32) {
+        self.treasure_prices.insert(treasure_type, price);
+    }
+
+    /// Return what the vendor will pay for a treasure right now
+    ///
+    /// Each treasure they've already bought knocks a fifth off, and they can't
+    /// pay more than they have.
+    pub fn treasure_offer(&self, treasure_type: TreasureType) -> u32 {
+        let mut offer = self.treasure_price(treasure_type).unwrap_or(0);
+
+        for _ in 0..self.treasures_bought {
+            offer = offer * 4 / 5;
+        }
+
+        std::cmp::min(offer, self.cash)
+    }
+
+    /// Pay for a treasure
+    pub fn buy_treasure(&mut self, price: u32) {
+        self.cash -= std::cmp::min(price, self.cash);
+        self.treasures_bought += 1;
+    }
+
+    /// Remember seeing the player do something
+    pub fn witness(&mut self, deed: usize) {
+        self.witnessed.push(deed);
+    }
+
+    /// Return the things the vendor saw the player do
+    pub fn witnessed(&self) -> &[usize] {
+        &self.witnessed
+    }
+}
+
+impl Default for Vendor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sell_takes_stock_and_cash() {
+        let mut v = Vendor::new();
+
+        assert_eq!(v.stock(Goods::Lamp), 1);
+        assert!(v.sell(Goods::Lamp, 20));
+        assert_eq!(v.stock(Goods::Lamp), 0);
+        assert_eq!(v.cash(), VENDOR_CASH + 20);
+
+        // Out of stock
+        assert!(!v.sell(Goods::Lamp, 20));
+        assert_eq!(v.cash(), VENDOR_CASH + 20);
+    }
+
+    #[test]
+    fn restock_brings_one_more_of_everything() {
+        let mut v = Vendor::new();
+        let leather = Goods::Armor(ArmorType::Leather);
+
+        v.sell(leather, 10);
+        v.sell(leather, 10);
+        v.sell(Goods::Lamp, 20);
+
+        // Not long enough yet
+        v.restock(RESTOCK_TURNS - 1);
+        assert_eq!(v.stock(leather), 0);
+
+        v.restock(RESTOCK_TURNS);
+        assert_eq!(v.stock(leather), 1);
+        assert_eq!(v.stock(Goods::Lamp), 1);
+
+        // Never more than a full stock
+        v.restock(RESTOCK_TURNS * 10);
+        assert_eq!(v.stock(leather), 2);
+        assert_eq!(v.stock(Goods::Lamp), 1);
+    }
+
+    #[test]
+    fn restock_counts_from_the_last_restock() {
+        let mut v = Vendor::new();
+        let dagger = Goods::Weapon(WeaponType::Dagger);
+
+        v.sell(dagger, 10);
+        v.sell(dagger, 10);
+
+        v.restock(RESTOCK_TURNS + 30);
+        v.restock(RESTOCK_TURNS + 40);
+        assert_eq!(v.stock(dagger), 1);
+
+        // The leftover 30 turns count towards the next one
+        v.restock(RESTOCK_TURNS * 2);
+        assert_eq!(v.stock(dagger), 2);
+    }
+
+    #[test]
+    fn restock_tops_up_the_cash() {
+        let mut v = Vendor::new();
+
+        v.buy_treasure(VENDOR_CASH);
+        assert_eq!(v.cash(), 0);
+
+        v.restock(RESTOCK_TURNS * 2);
+        assert_eq!(v.cash(), 2000);
+
+        v.restock(RESTOCK_TURNS * 100);
+        assert_eq!(v.cash(), VENDOR_CASH);
+    }
+
+    #[test]
+    fn treasure_offer_drops_with_each_treasure_bought() {
+        let mut v = Vendor::new();
+
+        assert_eq!(v.treasure_offer(TreasureType::RubyRed), 0);
+
+        v.set_treasure_price(TreasureType::RubyRed, 5000);
+        assert_eq!(v.treasure_price(TreasureType::RubyRed), Some(5000));
+        assert_eq!(v.treasure_offer(TreasureType::RubyRed), 5000);
+
+        v.buy_treasure(1000);
+        assert_eq!(v.treasure_offer(TreasureType::RubyRed), 4000);
+
+        v.buy_treasure(1000);
+        assert_eq!(v.treasure_offer(TreasureType::RubyRed), 3200);
+    }
+
+    #[test]
+    fn treasure_offer_is_limited_by_cash() {
+        let mut v = Vendor::new();
+
+        v.set_treasure_price(TreasureType::Silmaril, 9000);
+        v.buy_treasure(VENDOR_CASH - 100);
+
+        assert_eq!(v.treasure_offer(TreasureType::Silmaril), 100);
+    }
+
+    #[test]
+    fn buy_treasure_never_goes_below_no_cash() {
+        let mut v = Vendor::new();
+
+        v.buy_treasure(VENDOR_CASH + 500);
+
+        assert_eq!(v.cash(), 0);
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum WeaponType {
     None,
     Dagger,
@@ -6,35 +6,69 @@ pub enum WeaponType {
     Sword,
 }
 
+impl WeaponType {
+    /// Return the weapon's name
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponType::None => "NONE",
+            WeaponType::Dagger => "DAGGER",
+            WeaponType::Mace => "MACE",
+            WeaponType::Sword => "SWORD",
+        }
+    }
+
+    /// Return the weapon with a name
+    pub fn from_name(name: &str) -> Option<WeaponType> {
+        match name {
+            "NONE" => Some(WeaponType::None),
+            "DAGGER" => Some(WeaponType::Dagger),
+            "MACE" => Some(WeaponType::Mace),
+            "SWORD" => Some(WeaponType::Sword),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Weapon {
     weapon_type: WeaponType,
+    condition: u32,
 }
 
 impl Weapon {
     pub fn new(w: WeaponType) -> Weapon {
-        Weapon { weapon_type: w }
+        Weapon {
+            weapon_type: w,
+            condition: Weapon::max_condition(w),
+        }
     }
 
-    pub fn cost(w: WeaponType, is_vendor: bool) -> u32 {
-        let value;
+    /// Return how many hits a new weapon is good for
+    fn max_condition(w: WeaponType) -> u32 {
+        match w {
+            WeaponType::None => 0,
+            WeaponType::Dagger => 10,
+            WeaponType::Mace => 20,
+            WeaponType::Sword => 30,
+        }
+    }
 
+    pub fn cost(w: WeaponType, is_vendor: bool) -> u32 {
         if is_vendor {
-            value = match w {
+            match w {
                 WeaponType::None => 0,
                 WeaponType::Dagger => 1250,
                 WeaponType::Mace => 1500,
                 WeaponType::Sword => 2000,
             }
         } else {
-            value = match w {
+            match w {
                 WeaponType::None => 0,
                 WeaponType::Dagger => 10,
                 WeaponType::Mace => 20,
                 WeaponType::Sword => 30,
             }
         }
-
-        value
     }
 
     pub fn damage_by_type(w: WeaponType) -> u32 {
@@ -56,8 +90,56 @@ impl Weapon {
         }
     }
 
+    /// Return damage, which is a point less once the weapon is worn
+    ///
+    /// A worn weapon still does at least a point of damage.
     pub fn damage(&self) -> u32 {
-        Weapon::damage_by_type(self.weapon_type)
+        let damage = Weapon::damage_by_type(self.weapon_type);
+
+        if self.is_worn() {
+            std::cmp::max(damage - 1, 1)
+        } else {
+            damage
+        }
+    }
+
+    /// Return how many more hits the weapon is good for
+    pub fn condition(&self) -> u32 {
+        self.condition
+    }
+
+    /// True if the weapon is down to half its condition or less
+    pub fn is_worn(&self) -> bool {
+        self.weapon_type != WeaponType::None
+            && self.condition * 2 <= Weapon::max_condition(self.weapon_type)
+    }
+
+    /// Wear the weapon down
+    ///
+    /// Return true if it's worn out
+    pub fn wear(&mut self, amount: u32) -> bool {
+        if amount >= self.condition {
+            self.condition = 0;
+            return true;
+        }
+
+        self.condition -= amount;
+
+        false
+    }
+
+    /// Repair the weapon, but not past new
+    ///
+    /// Return how much was repaired.
+    pub fn repair(&mut self, amount: u32) -> u32 {
+        let repaired = std::cmp::min(
+            amount,
+            Weapon::max_condition(self.weapon_type) - self.condition,
+        );
+
+        self.condition += repaired;
+
+        repaired
     }
 
     pub fn weapon_type(&self) -> WeaponType {